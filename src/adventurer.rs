@@ -0,0 +1,434 @@
+use std::collections::HashSet;
+
+use macroquad::prelude::*;
+
+use crate::{
+    game::MapInfo,
+    map::{Dungeon, TraversalInfo},
+};
+
+// ticks between each entrance letting in a new adventurer
+pub const SPAWN_INTERVAL_TICKS: u32 = 50;
+// ticks an adventurer spends looking around a room before moving on
+pub const ROOM_TICKS: u32 = 10;
+// rooms an adventurer is willing to explore before heading back out
+pub const STARTING_STAMINA: u32 = 12;
+// chance (out of 1000) per level of depth that a newly explored room kills the adventurer
+pub const DEATH_CHANCE_PER_DEPTH: u32 = 5;
+pub const MAX_DEATH_CHANCE: u32 = 250;
+
+pub const ADVENTURER_EXPLORING: Color = YELLOW;
+pub const ADVENTURER_RETURNING: Color = ORANGE;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdventurerState {
+    Exploring,
+    Returning,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Adventurer {
+    pub floor: usize,
+    pub row: usize,
+    pub col: usize,
+    pub state: AdventurerState,
+    pub stamina: u32,
+    pub ticks_in_room: u32,
+    pub visited: HashSet<(usize, usize, usize)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StepOutcome {
+    Stayed,
+    Left,
+    Died,
+}
+
+/// What happened to the adventurers during one or more ticks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TickReport {
+    pub spawned: u32,
+    pub left: u32,
+    pub died: u32,
+}
+
+impl std::ops::AddAssign for TickReport {
+    fn add_assign(&mut self, rhs: Self) {
+        self.spawned += rhs.spawned;
+        self.left += rhs.left;
+        self.died += rhs.died;
+    }
+}
+
+/// Small xorshift generator, the simulation has to be reproducible from its seed so it can't share macroquad's global rng
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimRng {
+    pub state: u64,
+}
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift never leaves 0
+        return SimRng { state: seed.max(1) };
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        return (self.state >> 32) as u32;
+    }
+
+    /// A number in [0, bound)
+    pub fn below(&mut self, bound: u32) -> u32 {
+        return self.next_u32() % bound.max(1);
+    }
+}
+
+pub struct Adventurers {
+    pub active: Vec<Adventurer>,
+    pub spawn_timer: u32,
+    pub rng: SimRng,
+}
+
+impl Adventurer {
+    pub fn new(floor: usize, row: usize, col: usize) -> Self {
+        return Adventurer {
+            floor,
+            row,
+            col,
+            state: AdventurerState::Exploring,
+            stamina: STARTING_STAMINA,
+            ticks_in_room: 0,
+            visited: HashSet::from([(floor, row, col)]),
+        };
+    }
+
+    fn depth(&self, traversal_info: &[MapInfo<TraversalInfo>]) -> Option<i32> {
+        return traversal_info[self.floor][(self.row, self.col)].map(|ti| ti.depth);
+    }
+
+    /// Moves on to the next room once enough time has been spent in the current one
+    fn step(
+        &mut self,
+        dungeon: &Dungeon,
+        traversal_info: &[MapInfo<TraversalInfo>],
+        entrances: &[(usize, usize)],
+        rng: &mut SimRng,
+    ) -> StepOutcome {
+        // the room was dug out from under them, nothing to do but run
+        if dungeon.floors[self.floor].rooms[(self.row, self.col)].is_none() {
+            return StepOutcome::Left;
+        }
+        if self.ticks_in_room < ROOM_TICKS {
+            return StepOutcome::Stayed;
+        }
+        self.ticks_in_room = 0;
+
+        if self.state == AdventurerState::Returning
+            && self.floor == 0
+            && entrances.contains(&(self.row, self.col))
+        {
+            return StepOutcome::Left;
+        }
+        // walled in or cut off from every entrance by an edit, there is no way back out for them. Any other room that
+        // can be reached has a way back towards an entrance
+        if self.depth(traversal_info).is_none() {
+            return StepOutcome::Died;
+        }
+
+        let Some((floor, row, col)) = self.choose_next(dungeon, traversal_info, rng) else {
+            self.state = AdventurerState::Returning;
+            return StepOutcome::Stayed;
+        };
+        self.floor = floor;
+        self.row = row;
+        self.col = col;
+
+        if self.state == AdventurerState::Exploring {
+            self.stamina = self.stamina.saturating_sub(1);
+            if self.stamina == 0 {
+                self.state = AdventurerState::Returning;
+            }
+        }
+        // only rooms they haven't seen before can surprise them
+        if self.visited.insert((floor, row, col)) {
+            let depth = self.depth(traversal_info).unwrap_or(0).max(0) as u32;
+            let death_chance = (depth * DEATH_CHANCE_PER_DEPTH).min(MAX_DEATH_CHANCE);
+            if rng.below(1000) < death_chance {
+                return StepOutcome::Died;
+            }
+        }
+        return StepOutcome::Stayed;
+    }
+
+    /// Picks the room to move to next, None when there is nowhere to go
+    fn choose_next(
+        &self,
+        dungeon: &Dungeon,
+        traversal_info: &[MapInfo<TraversalInfo>],
+        rng: &mut SimRng,
+    ) -> Option<(usize, usize, usize)> {
+        let neighbours = dungeon.neighbours(self.floor, self.row, self.col);
+        if neighbours.is_empty() {
+            return None;
+        }
+        let candidates: Vec<_> = match self.state {
+            AdventurerState::Exploring => neighbours
+                .iter()
+                .filter(|x| !self.visited.contains(x))
+                .copied()
+                .collect(),
+            // head towards the closest entrance
+            AdventurerState::Returning => {
+                let current_depth = self.depth(traversal_info).unwrap_or(i32::MAX);
+                neighbours
+                    .iter()
+                    .filter(|(floor, row, col)| {
+                        traversal_info[*floor][(*row, *col)]
+                            .is_some_and(|ti| ti.depth < current_depth)
+                    })
+                    .copied()
+                    .collect()
+            }
+        };
+        let options = if candidates.is_empty() {
+            &neighbours
+        } else {
+            &candidates
+        };
+        return Some(options[rng.below(options.len() as u32) as usize]);
+    }
+}
+
+impl Adventurers {
+    /// Resolves `ticks` worth of adventuring at once: everyone already inside and everyone who would have arrived in
+    /// that time runs their whole expedition instantly instead of waiting out each room
+    pub fn advance(
+        &mut self,
+        ticks: u64,
+        dungeon: &Dungeon,
+        traversal_info: &[MapInfo<TraversalInfo>],
+        entrances: &[(usize, usize)],
+    ) -> TickReport {
+        let mut report = TickReport::default();
+        let mut expeditions: Vec<Adventurer> = self.active.drain(..).collect();
+
+        let total_ticks = self.spawn_timer as u64 + ticks;
+        let waves = total_ticks / SPAWN_INTERVAL_TICKS as u64;
+        self.spawn_timer = (total_ticks % SPAWN_INTERVAL_TICKS as u64) as u32;
+        for _ in 0..waves {
+            for (row, col) in entrances.iter() {
+                if dungeon.floors[0].rooms[(*row, *col)].is_some() {
+                    expeditions.push(Adventurer::new(0, *row, *col));
+                    report.spawned += 1;
+                }
+            }
+        }
+
+        // without this, someone stuck wandering an area with no way back out would never finish
+        let max_steps = STARTING_STAMINA as usize
+            + dungeon
+                .floors
+                .iter()
+                .map(|x| x.width() * x.height())
+                .sum::<usize>();
+        for mut adventurer in expeditions {
+            let mut outcome = StepOutcome::Stayed;
+            for _ in 0..max_steps {
+                adventurer.ticks_in_room = ROOM_TICKS;
+                outcome = adventurer.step(dungeon, traversal_info, entrances, &mut self.rng);
+                if outcome != StepOutcome::Stayed {
+                    break;
+                }
+            }
+            match outcome {
+                StepOutcome::Died => report.died += 1,
+                StepOutcome::Stayed | StepOutcome::Left => report.left += 1,
+            }
+        }
+        return report;
+    }
+
+    pub fn new(seed: u64) -> Self {
+        return Adventurers {
+            active: Vec::new(),
+            spawn_timer: 0,
+            rng: SimRng::new(seed),
+        };
+    }
+
+    /// Advances every adventurer by one tick, entrances are on floor 0 and rooms are connected the same way traversal
+    /// connects them
+    pub fn tick(
+        &mut self,
+        dungeon: &Dungeon,
+        traversal_info: &[MapInfo<TraversalInfo>],
+        entrances: &[(usize, usize)],
+    ) -> TickReport {
+        let mut report = TickReport::default();
+
+        self.spawn_timer += 1;
+        if self.spawn_timer >= SPAWN_INTERVAL_TICKS {
+            self.spawn_timer = 0;
+            for (row, col) in entrances.iter() {
+                if dungeon.floors[0].rooms[(*row, *col)].is_some() {
+                    self.active.push(Adventurer::new(0, *row, *col));
+                    report.spawned += 1;
+                }
+            }
+        }
+
+        let rng = &mut self.rng;
+        self.active.retain_mut(|adventurer| {
+            adventurer.ticks_in_room += 1;
+            match adventurer.step(dungeon, traversal_info, entrances, rng) {
+                StepOutcome::Stayed => true,
+                StepOutcome::Left => {
+                    report.left += 1;
+                    false
+                }
+                StepOutcome::Died => {
+                    report.died += 1;
+                    false
+                }
+            }
+        });
+
+        return report;
+    }
+
+    pub fn draw(&self, floor: usize, top_left: Vec2, scale: f32) {
+        for (i, adventurer) in self
+            .active
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.floor == floor)
+        {
+            // spread out adventurers sharing a room so they don't hide each other
+            let offset = (i % 4) as f32 * scale / 8.0 - scale * 3.0 / 16.0;
+            draw_circle(
+                top_left.x + (adventurer.col as f32 + 0.5) * scale + offset,
+                top_left.y + (adventurer.row as f32 + 0.5) * scale + offset,
+                scale / 10.0,
+                match adventurer.state {
+                    AdventurerState::Exploring => ADVENTURER_EXPLORING,
+                    AdventurerState::Returning => ADVENTURER_RETURNING,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adventurer::{
+        Adventurer, AdventurerState, ROOM_TICKS, SPAWN_INTERVAL_TICKS, STARTING_STAMINA, TickReport,
+    };
+    use crate::game::GameState;
+    use crate::map::room::room_type;
+
+    #[test]
+    fn test_spawn_at_entrances() {
+        let mut game = GameState::new(3, 1, 0, 1, room_type::ENTRANCE);
+        for _ in 1..SPAWN_INTERVAL_TICKS {
+            assert_eq!(game.tick().spawned, 0);
+        }
+        assert_eq!(game.tick().spawned, 1);
+        assert_eq!(game.adventurers.active.len(), 1);
+        let adventurer = &game.adventurers.active[0];
+        assert_eq!(
+            (adventurer.floor, adventurer.row, adventurer.col),
+            (0, 0, 1)
+        );
+    }
+
+    #[test]
+    fn test_lone_entrance_adventurers_leave() {
+        let mut game = GameState::new(1, 1, 0, 0, room_type::ENTRANCE);
+        let mut total = TickReport::default();
+        for _ in 0..SPAWN_INTERVAL_TICKS + ROOM_TICKS * 2 {
+            total += game.tick();
+        }
+        assert_eq!(total.spawned, 1);
+        assert_eq!(total.left, 1);
+        assert_eq!(total.died, 0);
+        assert!(game.adventurers.active.is_empty());
+    }
+
+    #[test]
+    fn test_returning_adventurer_walks_to_entrance() {
+        let mut game = GameState::new(4, 1, 0, 0, room_type::ENTRANCE);
+        game.update_room(0, 0, 1, Some(room_type::HALL.rotate_left()));
+        game.update_room(0, 0, 2, Some(room_type::HALL.rotate_left()));
+        game.update_room(0, 0, 3, Some(room_type::DEAD_END.rotate_left()));
+        assert!(game.traversal_info[0][(0, 3)].is_some());
+
+        let mut adventurer = Adventurer::new(0, 0, 3);
+        adventurer.state = AdventurerState::Returning;
+        adventurer.visited.extend((0..4).map(|col| (0, 0, col)));
+        game.adventurers.active.push(adventurer);
+
+        let mut total = TickReport::default();
+        for _ in 0..ROOM_TICKS * 3 {
+            total += game.tick();
+            assert_eq!(total.died, 0, "walking through known rooms is safe");
+        }
+        assert_eq!(game.adventurers.active[0].col, 0);
+        for _ in 0..ROOM_TICKS {
+            total += game.tick();
+        }
+        assert_eq!(total.left, 1);
+    }
+
+    #[test]
+    fn test_trapped_adventurers_die() {
+        let mut game = GameState::new(3, 1, 0, 0, room_type::ENTRANCE);
+        game.update_room(0, 0, 1, Some(room_type::HALL.rotate_left()));
+        game.update_room(0, 0, 2, Some(room_type::DEAD_END.rotate_left()));
+        let mut walled_in = Adventurer::new(0, 0, 1);
+        walled_in.state = AdventurerState::Returning;
+        game.adventurers.active.push(walled_in);
+        let mut cut_off = Adventurer::new(0, 0, 2);
+        cut_off.state = AdventurerState::Returning;
+        game.adventurers.active.push(cut_off);
+
+        // turning the hall walls in whoever is inside it and cuts the dead end off from the entrance
+        game.rotate_room(0, 0, 1, true).unwrap();
+        assert!(game.traversal_info[0][(0, 2)].is_none());
+        let mut total = TickReport::default();
+        for _ in 0..ROOM_TICKS {
+            total += game.tick();
+        }
+        assert_eq!(total.died, 2);
+        assert!(game.adventurers.active.is_empty());
+    }
+
+    #[test]
+    fn test_every_adventurer_accounted_for() {
+        let mut game = GameState::new(3, 3, 2, 1, room_type::ENTRANCE);
+        game.update_room(0, 2, 0, Some(room_type::L.rotate_left()));
+        game.update_room(0, 1, 0, Some(room_type::T.rotate_right()));
+        game.update_room(0, 0, 0, Some(room_type::L.rotate_right()));
+        game.update_room(0, 0, 1, Some(room_type::CROSSING));
+        game.update_room(0, 1, 1, Some(room_type::CROSSING));
+        game.update_room(0, 2, 2, Some(room_type::DEAD_END.rotate_left()));
+
+        let mut total = TickReport::default();
+        for _ in 0..SPAWN_INTERVAL_TICKS * 20 {
+            total += game.tick();
+            assert_eq!(
+                total.spawned,
+                total.left + total.died + game.adventurers.active.len() as u32
+            );
+            for adventurer in game.adventurers.active.iter() {
+                assert!(adventurer.stamina <= STARTING_STAMINA);
+                assert!(
+                    game.get_map_level(adventurer.floor).rooms[(adventurer.row, adventurer.col)]
+                        .is_some()
+                );
+            }
+        }
+        assert!(total.left > 0);
+    }
+}
@@ -0,0 +1,144 @@
+use macroquad::prelude::*;
+
+// pixels per room
+const MIN_ZOOM: f32 = 12.0;
+const MAX_ZOOM: f32 = 400.0;
+// how much of the screen a fitted map takes up
+const FIT_MARGIN: f32 = 0.8;
+// screen pixels per second when panning with the keyboard
+const PAN_SPEED: f32 = 600.0;
+// zoom change for each notch of the mouse wheel
+const WHEEL_ZOOM_STEP: f32 = 1.1;
+// a right button press that moves less than this many pixels is a click rather than a drag
+const CLICK_DISTANCE: f32 = 4.0;
+
+const PAN_KEYS: [(KeyCode, Vec2); 4] = [
+    (KeyCode::W, Vec2::new(0.0, 1.0)),
+    (KeyCode::A, Vec2::new(1.0, 0.0)),
+    (KeyCode::S, Vec2::new(0.0, -1.0)),
+    (KeyCode::D, Vec2::new(-1.0, 0.0)),
+];
+
+/// Where the map is seen from. Positions on the map are measured in rooms from its top left corner
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    // the point on the map in the middle of the screen
+    pub centre: Vec2,
+    // pixels per room
+    pub zoom: f32,
+    // screen position the mouse was dragging from last frame
+    drag_from: Option<Vec2>,
+    // how far the mouse has moved since the right button last went down
+    drag_distance: f32,
+}
+
+impl Camera {
+    /// Shows the whole of a `width` x `height` map on a screen of the given size
+    pub fn fit(width: usize, height: usize, screen: Vec2) -> Self {
+        let size = Vec2::new(width.max(1) as f32, height.max(1) as f32);
+        let zoom = (screen * FIT_MARGIN / size).min_element();
+        return Camera {
+            centre: size / 2.0,
+            zoom: zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+            drag_from: None,
+            drag_distance: 0.0,
+        };
+    }
+
+    /// Screen position of the map's top left corner
+    pub fn map_top_left(&self, screen: Vec2) -> Vec2 {
+        return screen / 2.0 - self.centre * self.zoom;
+    }
+
+    pub fn screen_to_map(&self, point: Vec2, screen: Vec2) -> Vec2 {
+        return (point - self.map_top_left(screen)) / self.zoom;
+    }
+
+    /// Moves the view along with something dragged `delta` pixels across the screen
+    pub fn pan(&mut self, delta: Vec2) {
+        self.centre -= delta / self.zoom;
+    }
+
+    /// Zooms by `factor`, keeping the part of the map under `point` where it is on screen
+    pub fn zoom_at(&mut self, factor: f32, point: Vec2, screen: Vec2) {
+        let before = self.screen_to_map(point, screen);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let after = self.screen_to_map(point, screen);
+        self.centre += before - after;
+    }
+
+    /// WASD or dragging with the right mouse button pans, the wheel zooms and F fits the map to the screen
+    pub fn handle_input(&mut self, map_width: usize, map_height: usize) {
+        let screen = Vec2::new(screen_width(), screen_height());
+        for (key, direction) in PAN_KEYS {
+            if is_key_down(key) {
+                self.pan(direction * PAN_SPEED * get_frame_time());
+            }
+        }
+
+        let mouse = Vec2::from(mouse_position());
+        if is_mouse_button_pressed(MouseButton::Right) {
+            self.drag_distance = 0.0;
+        }
+        self.drag_from = if is_mouse_button_down(MouseButton::Right) {
+            if let Some(from) = self.drag_from {
+                self.pan(mouse - from);
+                self.drag_distance += (mouse - from).length();
+            }
+            Some(mouse)
+        } else {
+            None
+        };
+
+        let (_, wheel) = mouse_wheel();
+        if wheel != 0.0 {
+            self.zoom_at(WHEEL_ZOOM_STEP.powf(wheel.signum()), mouse, screen);
+        }
+
+        if is_key_released(KeyCode::F) {
+            *self = Camera::fit(map_width, map_height, screen);
+        }
+    }
+
+    /// Whether the right button was let go this frame without dragging the view
+    pub fn right_clicked(&self) -> bool {
+        return is_mouse_button_released(MouseButton::Right) && self.drag_distance < CLICK_DISTANCE;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::prelude::*;
+
+    use crate::camera::Camera;
+
+    #[test]
+    fn test_fit_centres_the_map() {
+        let screen = Vec2::new(1000.0, 500.0);
+        let camera = Camera::fit(8, 4, screen);
+        assert_eq!(camera.zoom, 100.0);
+        assert_eq!(camera.map_top_left(screen), Vec2::new(100.0, 50.0));
+        assert_eq!(
+            camera.screen_to_map(Vec2::new(500.0, 250.0), screen),
+            Vec2::new(4.0, 2.0)
+        );
+    }
+
+    #[test]
+    fn test_zoom_keeps_point_under_cursor() {
+        let screen = Vec2::new(1000.0, 500.0);
+        let mut camera = Camera::fit(8, 4, screen);
+        let cursor = Vec2::new(730.0, 120.0);
+        let under_cursor = camera.screen_to_map(cursor, screen);
+        camera.zoom_at(2.0, cursor, screen);
+        assert_eq!(camera.zoom, 200.0);
+        assert!((camera.screen_to_map(cursor, screen) - under_cursor).length() < 1e-4);
+
+        camera.pan(Vec2::new(200.0, 0.0));
+        assert!(((camera.screen_to_map(cursor, screen) - under_cursor).x + 1.0).abs() < 1e-4);
+
+        // zoom is clamped so the map never vanishes or fills the screen with a single room
+        camera.zoom_at(1000.0, cursor, screen);
+        assert_eq!(camera.zoom, 400.0);
+    }
+}
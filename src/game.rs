@@ -0,0 +1,1392 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    adventurer::{Adventurers, TickReport},
+    history::{History, RoomEdit, Step},
+    map::{
+        self, Cell, Direction, TraversalError, TraversalInfo, TraversalMode,
+        grid::Grid,
+        path::Path,
+        room::{self, SimpleRoomDrawInfo, Stairs},
+    },
+    resources::Resources,
+};
+
+pub const TICKS_PER_SECOND: u64 = 10;
+// real time covered by each simulation tick
+pub const TICK_SECONDS: f32 = 1.0 / TICKS_PER_SECOND as f32;
+// size of each step when catching up on time spent away
+pub const OFFLINE_STEP_TICKS: u64 = 60 * TICKS_PER_SECOND;
+// time away beyond this isn't credited
+pub const MAX_OFFLINE_SECONDS: u64 = 8 * 60 * 60;
+// shorter absences are still simulated but not worth a summary
+pub const MIN_OFFLINE_SUMMARY_SECONDS: u64 = 60;
+// ticks between each payout of the dungeon's income
+pub const INCOME_INTERVAL_TICKS: u32 = 10;
+pub const STARTING_RESOURCES: Resources = Resources::gold(50);
+// price of every new cell bought when expanding, for each floor
+pub const EXPANSION_COST_PER_CELL: Resources = Resources::gold(20);
+// price of turning a room into an extra entrance
+pub const ENTRANCE_COST: Resources = Resources::gold(200);
+// marks the rooms adventurers come in through
+pub const ENTRANCE_SYMBOL: char = 'E';
+
+pub type MapInfo<T> = Grid<T>;
+
+/// How much editing the dungeon costs, on top of each room's own build cost
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuildPolicy {
+    // share of a room's build cost given back when it is replaced or removed
+    pub refund_percent: u64,
+    pub rotation_cost: Resources,
+}
+
+impl Default for BuildPolicy {
+    fn default() -> Self {
+        return BuildPolicy {
+            refund_percent: 50,
+            rotation_cost: Resources::gold(1),
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditError {
+    CantAfford { cost: Resources },
+    NoRoom,
+    CantPlaceEntrance,
+    NotAnEntrance,
+    LastEntrance,
+    NothingToUndo,
+    NothingToRedo,
+    Indestructible,
+    NotNextToEachOther,
+    NoCorridorRoom,
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::CantAfford { cost } => write!(f, "can't afford {cost}"),
+            EditError::NoRoom => write!(f, "there is no room there"),
+            EditError::CantPlaceEntrance => {
+                write!(f, "entrances go in rooms without stairs on the top floor")
+            }
+            EditError::NotAnEntrance => write!(f, "there is no entrance there"),
+            EditError::LastEntrance => write!(f, "the dungeon needs at least one entrance"),
+            EditError::NothingToUndo => write!(f, "nothing to undo"),
+            EditError::NothingToRedo => write!(f, "nothing to redo"),
+            EditError::Indestructible => write!(f, "that room can't be built over or removed"),
+            EditError::NotNextToEachOther => write!(f, "corridors only join neighbouring rooms"),
+            EditError::NoCorridorRoom => {
+                write!(f, "no plain room has the exits that corridor needs")
+            }
+        }
+    }
+}
+
+/// What happened while the game wasn't running
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OfflineSummary {
+    pub seconds_away: u64,
+    pub seconds_simulated: u64,
+    pub earned: Resources,
+    pub adventurers: TickReport,
+}
+
+pub fn now_unix_secs() -> u64 {
+    return SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |x| x.as_secs());
+}
+
+pub struct GameState {
+    pub dungeon: map::Dungeon,
+    // one entry per floor of the dungeon
+    pub traversal_info: Vec<MapInfo<TraversalInfo>>,
+    // entrances are always on the top floor
+    pub entrance_rowcols: Vec<(usize, usize)>,
+    // rooms as they were before being made into entrances, to be put back when they stop being one
+    pub entrance_originals: BTreeMap<(usize, usize), SimpleRoomDrawInfo>,
+    pub adventurers: Adventurers,
+    pub resources: Resources,
+    pub income_timer: u32,
+    pub build_policy: BuildPolicy,
+    pub traversal_mode: TraversalMode,
+    // problems hit by the last traversal, whatever could still be reached is in traversal_info
+    pub traversal_errors: Vec<TraversalError>,
+    // wall clock time the simulation was last brought up to date
+    pub last_seen_unix_secs: u64,
+    // real time that hasn't added up to a whole tick yet
+    pub unsimulated_seconds: f32,
+    // room edits made by the player, for undo and redo
+    pub history: History,
+}
+
+impl GameState {
+    pub fn new(
+        width: usize,
+        height: usize,
+        initial_entrance_row: usize,
+        initial_entrance_col: usize,
+        initial_room: SimpleRoomDrawInfo,
+    ) -> Self {
+        let mut dungeon = map::Dungeon::new(width, height);
+        // like every entrance, the first one can't be removed
+        dungeon.floors[0].rooms[(initial_entrance_row, initial_entrance_col)] =
+            Some(SimpleRoomDrawInfo {
+                indestructible: true,
+                ..initial_room
+            });
+        return GameState::from_dungeon(
+            dungeon,
+            vec![(initial_entrance_row, initial_entrance_col)],
+        );
+    }
+
+    /// A fresh game around an already built dungeon, with everything else at its starting values
+    pub fn from_dungeon(dungeon: map::Dungeon, entrance_rowcols: Vec<(usize, usize)>) -> Self {
+        let top = &dungeon.floors[0];
+        let traversal_info = vec![Grid::new(top.width(), top.height()); dungeon.floors.len()];
+        let mut game = GameState {
+            dungeon,
+            traversal_info,
+            entrance_rowcols,
+            entrance_originals: BTreeMap::new(),
+            adventurers: Adventurers::new(0x5eed),
+            resources: STARTING_RESOURCES,
+            income_timer: 0,
+            build_policy: BuildPolicy::default(),
+            traversal_mode: TraversalMode::default(),
+            traversal_errors: Vec::new(),
+            last_seen_unix_secs: now_unix_secs(),
+            unsimulated_seconds: 0.0,
+            history: History::default(),
+        };
+        game.recalculate_traversal();
+        return game;
+    }
+
+    pub fn update_room(
+        &mut self,
+        floor: usize,
+        row: usize,
+        col: usize,
+        new_room: Option<SimpleRoomDrawInfo>,
+    ) {
+        self.dungeon.floors[floor].rooms[(row, col)] = new_room;
+        self.traversal_errors = self.dungeon.repair_traversal(
+            &mut self.traversal_info,
+            &self.entrance_rowcols,
+            self.traversal_mode,
+            (floor, row, col),
+        );
+        self.log_traversal_errors();
+    }
+
+    /// Puts each room in its cell if `pay` can be covered once `receive` has been added to the resources, changing
+    /// nothing when it can't
+    fn pay_for_rooms(
+        &mut self,
+        rooms: &[(Cell, Option<SimpleRoomDrawInfo>)],
+        pay: Resources,
+        receive: Resources,
+    ) -> Result<(), EditError> {
+        let mut available = self.resources + receive;
+        if !available.try_spend(pay) {
+            return Err(EditError::CantAfford { cost: pay });
+        }
+        self.resources = available;
+        for ((floor, row, col), room) in rooms.iter().copied() {
+            self.update_room(floor, row, col, room);
+        }
+        return Ok(());
+    }
+
+    /// What building `new_room` in the cell would change and cost, refunding part of the old room's cost
+    fn room_edit(
+        &self,
+        (floor, row, col): Cell,
+        new_room: Option<SimpleRoomDrawInfo>,
+    ) -> Result<RoomEdit, EditError> {
+        let old_room = self.dungeon.floors[floor].rooms[(row, col)];
+        if old_room.is_some_and(|x| x.indestructible) {
+            return Err(EditError::Indestructible);
+        }
+        return Ok(RoomEdit {
+            cell: (floor, row, col),
+            before: old_room,
+            after: new_room,
+            spent: new_room.map_or(Resources::ZERO, |x| x.cost),
+            refunded: old_room.map_or(Resources::ZERO, |x| {
+                x.cost.percent(self.build_policy.refund_percent)
+            }),
+        });
+    }
+
+    /// Builds `new_room` over whatever was in the cell (or clears it when None), paying for the new room after
+    /// refunding part of the old one's cost. Indestructible rooms stay where they are
+    pub fn build_room(
+        &mut self,
+        floor: usize,
+        row: usize,
+        col: usize,
+        new_room: Option<SimpleRoomDrawInfo>,
+    ) -> Result<(), EditError> {
+        let edit = self.room_edit((floor, row, col), new_room)?;
+        self.pay_for_rooms(&[(edit.cell, edit.after)], edit.spent, edit.refunded)?;
+        self.history.record(vec![edit]);
+        return Ok(());
+    }
+
+    /// Removes every room in the rectangle with corners `from` and `to` on one floor, refunding part of each one's
+    /// cost, and returns how many went. Indestructible rooms are left alone. Undo puts all of them back at once
+    pub fn clear_area(&mut self, floor: usize, from: (usize, usize), to: (usize, usize)) -> usize {
+        let mut step = Vec::new();
+        for row in from.0.min(to.0)..=from.0.max(to.0) {
+            for col in from.1.min(to.1)..=from.1.max(to.1) {
+                let Ok(edit) = self.room_edit((floor, row, col), None) else {
+                    continue;
+                };
+                if edit.before.is_some() {
+                    self.resources += edit.refunded;
+                    self.update_room(floor, row, col, None);
+                    step.push(edit);
+                }
+            }
+        }
+        let removed = step.len();
+        self.history.record(step);
+        return removed;
+    }
+
+    /// Turns the room in the cell a quarter turn, clockwise or anticlockwise, for [BuildPolicy::rotation_cost]
+    pub fn rotate_room(
+        &mut self,
+        floor: usize,
+        row: usize,
+        col: usize,
+        clockwise: bool,
+    ) -> Result<(), EditError> {
+        let room = self.dungeon.floors[floor].rooms[(row, col)].ok_or(EditError::NoRoom)?;
+        let cost = self.build_policy.rotation_cost;
+        let rotated = if clockwise {
+            room.rotate_right()
+        } else {
+            room.rotate_left()
+        };
+        self.pay_for_rooms(&[((floor, row, col), Some(rotated))], cost, Resources::ZERO)?;
+        self.history.record(vec![RoomEdit {
+            cell: (floor, row, col),
+            before: Some(room),
+            after: Some(rotated),
+            spent: cost,
+            refunded: Resources::ZERO,
+        }]);
+        return Ok(());
+    }
+
+    /// Joins the rooms in two neighbouring cells on one floor, building whichever of `candidates` has the exits each
+    /// needs, turned to fit. Only plain rooms without stairs, a symbol or production are built as corridor. Rooms
+    /// keep the exits they already had, and stairs, rooms with a symbol and indestructible rooms are left as they
+    /// are. Nothing is built unless both rooms can be
+    pub fn extend_corridor(
+        &mut self,
+        floor: usize,
+        from: (usize, usize),
+        to: (usize, usize),
+        candidates: &[SimpleRoomDrawInfo],
+    ) -> Result<(), EditError> {
+        let side = Direction::between(from, to).ok_or(EditError::NotNextToEachOther)?;
+        let plain = candidates
+            .iter()
+            .copied()
+            .filter(|x| x.symbol.is_none() && x.production == Resources::ZERO);
+        let mut step = Vec::new();
+        for ((row, col), side) in [(from, side), (to, side.opposite())] {
+            let current = self.dungeon.floors[floor].rooms[(row, col)];
+            if current.is_some_and(|x| {
+                x.has_exit(side) || x.stairs.is_some() || x.symbol.is_some() || x.indestructible
+            }) {
+                continue;
+            }
+            let wanted = |x| x == side || current.is_some_and(|room| room.has_exit(x));
+            let room = room::with_exits(plain.clone(), wanted).ok_or(EditError::NoCorridorRoom)?;
+            step.push(self.room_edit((floor, row, col), Some(room))?);
+        }
+        if step.is_empty() {
+            return Ok(());
+        }
+        let rooms: Vec<_> = step.iter().map(|x| (x.cell, x.after)).collect();
+        let (spent, refunded) = step_totals(&step);
+        self.pay_for_rooms(&rooms, spent, refunded)?;
+        self.history.record(step);
+        return Ok(());
+    }
+
+    /// Takes back the latest step of room edits, returning what it cost and taking back what it refunded. Fails
+    /// without changing anything when a refund has already been spent
+    pub fn undo(&mut self) -> Result<(), EditError> {
+        self.history.end_step();
+        let step = self.history.next_undo().ok_or(EditError::NothingToUndo)?;
+        let rooms: Vec<_> = step.iter().rev().map(|x| (x.cell, x.before)).collect();
+        let (spent, refunded) = step_totals(step);
+        self.pay_for_rooms(&rooms, refunded, spent)?;
+        self.history.undone();
+        return Ok(());
+    }
+
+    /// Makes the latest undone step again, at the same price as the first time
+    pub fn redo(&mut self) -> Result<(), EditError> {
+        self.history.end_step();
+        let step = self.history.next_redo().ok_or(EditError::NothingToRedo)?;
+        let rooms: Vec<_> = step.iter().map(|x| (x.cell, x.after)).collect();
+        let (spent, refunded) = step_totals(step);
+        self.pay_for_rooms(&rooms, spent, refunded)?;
+        self.history.redone();
+        return Ok(());
+    }
+
+    /// Turns the room in the cell into another entrance for [ENTRANCE_COST], which can't be built over or removed
+    /// while it stays an entrance
+    pub fn add_entrance(&mut self, floor: usize, row: usize, col: usize) -> Result<(), EditError> {
+        if floor != 0 {
+            return Err(EditError::CantPlaceEntrance);
+        }
+        let room = self.dungeon.floors[0].rooms[(row, col)].ok_or(EditError::NoRoom)?;
+        if room.stairs.is_some() || self.entrance_rowcols.contains(&(row, col)) {
+            return Err(EditError::CantPlaceEntrance);
+        }
+        if !self.resources.try_spend(ENTRANCE_COST) {
+            return Err(EditError::CantAfford {
+                cost: ENTRANCE_COST,
+            });
+        }
+        self.dungeon.floors[0].rooms[(row, col)] = Some(SimpleRoomDrawInfo {
+            symbol: Some(ENTRANCE_SYMBOL),
+            indestructible: true,
+            ..room
+        });
+        self.entrance_rowcols.push((row, col));
+        self.entrance_originals.insert((row, col), room);
+        // undoing edits to an entrance's room could leave an entrance without its symbol, or a symbol without its
+        // entrance
+        self.history.clear();
+        self.recalculate_traversal();
+        return Ok(());
+    }
+
+    /// Turns an entrance back into a plain room, refunding part of [ENTRANCE_COST]. The last entrance can't go
+    pub fn remove_entrance(
+        &mut self,
+        floor: usize,
+        row: usize,
+        col: usize,
+    ) -> Result<(), EditError> {
+        let index = self
+            .entrance_rowcols
+            .iter()
+            .position(|x| floor == 0 && *x == (row, col))
+            .ok_or(EditError::NotAnEntrance)?;
+        if self.entrance_rowcols.len() == 1 {
+            return Err(EditError::LastEntrance);
+        }
+        self.entrance_rowcols.remove(index);
+        self.resources += ENTRANCE_COST.percent(self.build_policy.refund_percent);
+        let original = self.entrance_originals.remove(&(row, col));
+        if let Some(room) = self.dungeon.floors[0].rooms[(row, col)].as_mut() {
+            // the room may have been turned since, so only what becoming an entrance changed is put back
+            if let Some(original) = original {
+                room.symbol = original.symbol;
+                room.indestructible = original.indestructible;
+            } else {
+                // entrances the game started with were never anything else
+                room.indestructible = false;
+                if room.symbol == Some(ENTRANCE_SYMBOL) {
+                    room.symbol = None;
+                }
+            }
+        }
+        self.history.clear();
+        self.recalculate_traversal();
+        return Ok(());
+    }
+
+    /// Advances the simulation by one tick of [TICK_SECONDS], independent of rendering
+    pub fn tick(&mut self) -> TickReport {
+        let report =
+            self.adventurers
+                .tick(&self.dungeon, &self.traversal_info, &self.entrance_rowcols);
+        self.resources.souls += report.died as u64;
+
+        self.income_timer += 1;
+        if self.income_timer >= INCOME_INTERVAL_TICKS {
+            self.income_timer = 0;
+            self.resources += self.income();
+        }
+        return report;
+    }
+
+    /// Runs as many ticks as fit into the real time that has passed, carrying the remainder over to the next call
+    pub fn update(&mut self, elapsed_seconds: f32) -> TickReport {
+        let mut report = TickReport::default();
+        self.unsimulated_seconds += elapsed_seconds;
+        while self.unsimulated_seconds >= TICK_SECONDS {
+            report += self.tick();
+            self.unsimulated_seconds -= TICK_SECONDS;
+        }
+        return report;
+    }
+
+    /// Advances the simulation by `ticks` in one go, paying out income for every interval covered and resolving
+    /// adventurers as whole expeditions rather than tick by tick
+    pub fn advance(&mut self, ticks: u64) -> TickReport {
+        let report = self.adventurers.advance(
+            ticks,
+            &self.dungeon,
+            &self.traversal_info,
+            &self.entrance_rowcols,
+        );
+        self.resources.souls += report.died as u64;
+
+        let total_ticks = self.income_timer as u64 + ticks;
+        self.income_timer = (total_ticks % INCOME_INTERVAL_TICKS as u64) as u32;
+        self.resources += self.income() * (total_ticks / INCOME_INTERVAL_TICKS as u64);
+        return report;
+    }
+
+    /// Credits the time since [GameState::last_seen_unix_secs] (up to [MAX_OFFLINE_SECONDS]) in steps of
+    /// [OFFLINE_STEP_TICKS], returning a summary when the absence was long enough to be worth telling the player about
+    pub fn catch_up(&mut self, now_unix_secs: u64) -> Option<OfflineSummary> {
+        let seconds_away = now_unix_secs.saturating_sub(self.last_seen_unix_secs);
+        let seconds_simulated = seconds_away.min(MAX_OFFLINE_SECONDS);
+        self.last_seen_unix_secs = now_unix_secs;
+
+        let resources_before = self.resources;
+        let mut adventurers = TickReport::default();
+        let mut remaining_ticks = seconds_simulated * TICKS_PER_SECOND;
+        while remaining_ticks > 0 {
+            let step = remaining_ticks.min(OFFLINE_STEP_TICKS);
+            adventurers += self.advance(step);
+            remaining_ticks -= step;
+        }
+
+        if seconds_simulated < MIN_OFFLINE_SUMMARY_SECONDS {
+            return None;
+        }
+        return Some(OfflineSummary {
+            seconds_away,
+            seconds_simulated,
+            earned: self.resources.saturating_sub(resources_before),
+            adventurers,
+        });
+    }
+
+    /// What the dungeon pays out every [INCOME_INTERVAL_TICKS], rooms only earn anything while they can be reached
+    /// from an entrance, with deeper rooms earning more gold and rooms below the top floor also gathering mana, on top
+    /// of whatever their type produces
+    pub fn income(&self) -> Resources {
+        return self
+            .traversal_info
+            .iter()
+            .flat_map(|x| x.iter_some())
+            .map(|(_, _, ti)| {
+                Resources {
+                    gold: ti.depth.max(0) as u64 + 1,
+                    mana: ti.floor as u64,
+                    souls: 0,
+                } + ti.room_info.production
+            })
+            .fold(Resources::ZERO, |total, x| total + x);
+    }
+
+    /// Depth of the deepest room reachable from an entrance, on any floor
+    pub fn max_depth(&self) -> i32 {
+        return self
+            .traversal_info
+            .iter()
+            .flat_map(|x| x.iter_some())
+            .map(|(_, _, ti)| ti.depth)
+            .max()
+            .unwrap_or(0);
+    }
+
+    pub fn floor_count(&self) -> usize {
+        return self.dungeon.floors.len();
+    }
+
+    /// Digs a new, empty floor below the deepest one
+    pub fn add_floor(&mut self) {
+        self.dungeon.add_floor();
+        let top = &self.traversal_info[0];
+        self.traversal_info
+            .push(Grid::new(top.width(), top.height()));
+    }
+
+    pub fn expansion_cost(&self, side: Direction, count: usize) -> Resources {
+        let level = self.get_map_level(0);
+        let new_cells = match side {
+            Direction::Left | Direction::Right => level.height() * count,
+            Direction::Top | Direction::Bottom => level.width() * count,
+        };
+        return EXPANSION_COST_PER_CELL * (new_cells * self.floor_count()) as u64;
+    }
+
+    /// Price of digging another floor, the same per cell as expanding
+    pub fn floor_cost(&self) -> Resources {
+        let level = self.get_map_level(0);
+        return EXPANSION_COST_PER_CELL * (level.width() * level.height()) as u64;
+    }
+
+    /// Spends resources on digging a new floor below the deepest one, returns false without digging when they can't
+    /// be afforded
+    pub fn buy_floor(&mut self) -> bool {
+        if !self.resources.try_spend(self.floor_cost()) {
+            return false;
+        }
+        self.add_floor();
+        return true;
+    }
+
+    /// Spends resources on expanding the dungeon, returns false without expanding when they can't be afforded
+    pub fn buy_expansion(&mut self, side: Direction, count: usize) -> bool {
+        if !self.resources.try_spend(self.expansion_cost(side, count)) {
+            return false;
+        }
+        self.expand(side, count);
+        return true;
+    }
+
+    /// Grows the playable area by `count` rows or columns on the given side, everything already placed
+    /// keeps its position relative to the other rooms
+    pub fn expand(&mut self, side: Direction, count: usize) {
+        self.dungeon.expand(side, count);
+        for floor_info in self.traversal_info.iter_mut() {
+            floor_info.expand(side, count);
+        }
+        // adventurers walk with the rooms they are in
+        match side {
+            Direction::Top => {
+                self.entrance_rowcols
+                    .iter_mut()
+                    .for_each(|(row, _)| *row += count);
+                self.entrance_originals = std::mem::take(&mut self.entrance_originals)
+                    .into_iter()
+                    .map(|((row, col), room)| ((row + count, col), room))
+                    .collect();
+                for adventurer in self.adventurers.active.iter_mut() {
+                    adventurer.row += count;
+                    adventurer.visited = adventurer
+                        .visited
+                        .drain()
+                        .map(|(floor, row, col)| (floor, row + count, col))
+                        .collect();
+                }
+            }
+            Direction::Left => {
+                self.entrance_rowcols
+                    .iter_mut()
+                    .for_each(|(_, col)| *col += count);
+                self.entrance_originals = std::mem::take(&mut self.entrance_originals)
+                    .into_iter()
+                    .map(|((row, col), room)| ((row, col + count), room))
+                    .collect();
+                for adventurer in self.adventurers.active.iter_mut() {
+                    adventurer.col += count;
+                    adventurer.visited = adventurer
+                        .visited
+                        .drain()
+                        .map(|(floor, row, col)| (floor, row, col + count))
+                        .collect();
+                }
+            }
+            Direction::Bottom | Direction::Right => {}
+        }
+        self.history.expand(side, count);
+        self.recalculate_traversal();
+    }
+
+    /// Switches how depths are worked out, recalculating every cell's traversal info
+    pub fn set_traversal_mode(&mut self, mode: TraversalMode) {
+        self.traversal_mode = mode;
+        self.recalculate_traversal();
+    }
+
+    fn recalculate_traversal(&mut self) {
+        // clear the traversal map
+        self.traversal_info.iter_mut().for_each(|x| x.clear());
+        // every entrance starts at depth 0 in the same pass, so each room ends up with its closest entrance
+        let traversal_info = &mut self.traversal_info;
+        self.traversal_errors =
+            self.dungeon
+                .traverse_entrances(&self.entrance_rowcols, self.traversal_mode, |ti| {
+                    traversal_info[ti.floor][(ti.row as usize, ti.col as usize)] = Some(ti)
+                });
+        self.log_traversal_errors();
+    }
+
+    // a broken entrance only loses the rooms reached through it, the rest of the dungeon keeps working
+    fn log_traversal_errors(&self) {
+        for e in self.traversal_errors.iter() {
+            macroquad::logging::warn!("{}", e);
+        }
+    }
+
+    /// The cheapest way from a room back towards the entrance: to the nearest entrance on the top floor, or to the
+    /// nearest stairs up that lead back to the entrance on lower floors
+    pub fn route_to_entrance(&self, floor: usize, row: usize, col: usize) -> Option<Path> {
+        let targets: Vec<(usize, usize)> = if floor == 0 {
+            self.entrance_rowcols.clone()
+        } else {
+            self.dungeon.floors[floor]
+                .rooms
+                .iter_some()
+                .filter(|(row, col, room)| {
+                    room.stairs == Some(Stairs::Up)
+                        && self.traversal_info[floor][(*row, *col)].is_some()
+                })
+                .map(|(row, col, _)| (row, col))
+                .collect()
+        };
+        return targets
+            .into_iter()
+            .filter_map(|target| self.dungeon.floors[floor].find_path((row, col), target))
+            .min_by_key(|x| x.cost);
+    }
+
+    pub fn get_map_level(&self, floor: usize) -> &map::MapLevel {
+        return &self.dungeon.floors[floor];
+    }
+}
+
+/// Everything spent and refunded over a step of edits
+fn step_totals(step: &Step) -> (Resources, Resources) {
+    return step
+        .iter()
+        .fold((Resources::ZERO, Resources::ZERO), |total, x| {
+            (total.0 + x.spent, total.1 + x.refunded)
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::adventurer::{Adventurer, SimRng};
+    use crate::game::{
+        ENTRANCE_COST, ENTRANCE_SYMBOL, EXPANSION_COST_PER_CELL, EditError, GameState,
+        INCOME_INTERVAL_TICKS, MAX_OFFLINE_SECONDS, STARTING_RESOURCES, TICKS_PER_SECOND,
+    };
+    use crate::history::MAX_HISTORY;
+    use crate::map::{
+        Direction, Dungeon, MapLevel, TraversalError, TraversalMode,
+        catalogue::RoomCatalogue,
+        room::{SimpleRoomDrawInfo, room_type},
+    };
+    use crate::resources::Resources;
+
+    fn depths(game: &GameState) -> Vec<(usize, isize, isize, i32)> {
+        let mut result: Vec<_> = game
+            .traversal_info
+            .iter()
+            .flat_map(|x| x.iter_some())
+            .map(|(_, _, ti)| (ti.floor, ti.row, ti.col, ti.depth))
+            .collect();
+        result.sort();
+        return result;
+    }
+
+    #[test]
+    fn test_expand_keeps_connectivity() {
+        let mut game = GameState::new(3, 2, 1, 1, room_type::ENTRANCE);
+        game.update_room(0, 1, 0, Some(room_type::DEAD_END.rotate_right()));
+        game.update_room(0, 0, 1, Some(room_type::HALL));
+        assert_eq!(
+            depths(&game),
+            vec![(0, 0, 1, 1), (0, 1, 0, 1), (0, 1, 1, 0)]
+        );
+
+        game.expand(Direction::Top, 2);
+        game.expand(Direction::Left, 1);
+        game.expand(Direction::Right, 3);
+        game.expand(Direction::Bottom, 1);
+
+        assert_eq!(game.get_map_level(0).width(), 7);
+        assert_eq!(game.get_map_level(0).height(), 5);
+        assert_eq!(game.traversal_info[0].width(), 7);
+        assert_eq!(game.traversal_info[0].height(), 5);
+        assert_eq!(game.entrance_rowcols, vec![(3, 2)]);
+        assert_eq!(
+            game.get_map_level(0).rooms[(3, 2)],
+            Some(room_type::ENTRANCE)
+        );
+        assert_eq!(
+            depths(&game),
+            vec![(0, 2, 2, 1), (0, 3, 1, 1), (0, 3, 2, 0)]
+        );
+    }
+
+    #[test]
+    fn test_expand_moves_adventurers() {
+        let mut game = GameState::new(3, 2, 1, 1, room_type::ENTRANCE);
+        game.update_room(0, 1, 0, Some(room_type::DEAD_END.rotate_right()));
+        let mut adventurer = Adventurer::new(0, 1, 1);
+        adventurer.visited.insert((0, 1, 0));
+        game.adventurers.active.push(adventurer);
+
+        game.expand(Direction::Top, 1);
+        game.expand(Direction::Left, 2);
+        game.expand(Direction::Bottom, 1);
+
+        let adventurer = &game.adventurers.active[0];
+        assert_eq!((adventurer.row, adventurer.col), (2, 3));
+        assert_eq!(adventurer.visited, HashSet::from([(0, 2, 3), (0, 2, 2)]));
+        // still standing in a room, so they don't run off
+        assert_eq!(game.tick().left, 0);
+        assert_eq!(game.adventurers.active.len(), 1);
+    }
+
+    #[test]
+    fn test_floors_follow_stairs() {
+        let mut game = GameState::new(3, 2, 1, 1, room_type::ENTRANCE);
+        game.add_floor();
+        assert_eq!(game.floor_count(), 2);
+
+        game.update_room(0, 1, 0, Some(room_type::STAIRS_DOWN.rotate_right()));
+        assert_eq!(depths(&game), vec![(0, 1, 0, 1), (0, 1, 1, 0)]);
+
+        game.update_room(1, 1, 0, Some(room_type::STAIRS_UP));
+        game.update_room(1, 0, 0, Some(room_type::HALL));
+        assert_eq!(
+            depths(&game),
+            vec![(0, 1, 0, 1), (0, 1, 1, 0), (1, 0, 0, 3), (1, 1, 0, 2)]
+        );
+        assert_eq!(game.max_depth(), 3);
+
+        // lower floors route back to the stairs, the top floor to the entrance
+        let route = game.route_to_entrance(1, 0, 0).unwrap();
+        assert_eq!(route.steps.last().map(|x| (x.row, x.col)), Some((1, 0)));
+        let route = game.route_to_entrance(0, 1, 0).unwrap();
+        assert_eq!(route.steps.last().map(|x| (x.row, x.col)), Some((1, 1)));
+
+        game.expand(Direction::Left, 1);
+        assert_eq!(game.get_map_level(1).width(), 4);
+        assert_eq!(
+            depths(&game),
+            vec![(0, 1, 1, 1), (0, 1, 2, 0), (1, 0, 1, 3), (1, 1, 1, 2)]
+        );
+    }
+
+    #[test]
+    fn test_multiple_entrances() {
+        // a corridor of crossings with the first entrance at one end
+        let mut game = GameState::new(5, 1, 0, 0, room_type::ENTRANCE);
+        for col in 1..5 {
+            game.update_room(0, 0, col, Some(room_type::CROSSING));
+        }
+        game.resources = ENTRANCE_COST * 2;
+
+        game.add_entrance(0, 0, 4).unwrap();
+        assert_eq!(game.resources, ENTRANCE_COST);
+        assert_eq!(
+            game.get_map_level(0).rooms[(0, 4)].unwrap().symbol,
+            Some(ENTRANCE_SYMBOL)
+        );
+        assert_eq!(
+            game.add_entrance(0, 0, 4),
+            Err(EditError::CantPlaceEntrance)
+        );
+        assert_eq!(
+            game.add_entrance(1, 0, 0),
+            Err(EditError::CantPlaceEntrance)
+        );
+
+        // every room is as deep as its closest entrance, the middle one is a tie that goes to the first by position
+        let closest: Vec<_> = game.traversal_info[0]
+            .iter_some()
+            .map(|(_, _, ti)| (ti.depth, ti.entrance))
+            .collect();
+        assert_eq!(
+            closest,
+            vec![
+                (0, (0, 0)),
+                (1, (0, 0)),
+                (2, (0, 0)),
+                (1, (0, 4)),
+                (0, (0, 4))
+            ]
+        );
+
+        game.remove_entrance(0, 0, 0).unwrap();
+        assert_eq!(
+            game.resources,
+            ENTRANCE_COST + ENTRANCE_COST.percent(game.build_policy.refund_percent)
+        );
+        assert_eq!(game.get_map_level(0).rooms[(0, 0)].unwrap().symbol, None);
+        assert_eq!(game.traversal_info[0][(0, 0)].unwrap().depth, 4);
+        assert_eq!(game.remove_entrance(0, 0, 0), Err(EditError::NotAnEntrance));
+        assert_eq!(game.remove_entrance(0, 0, 4), Err(EditError::LastEntrance));
+    }
+
+    #[test]
+    fn test_removed_entrance_restores_room() {
+        let vault = SimpleRoomDrawInfo {
+            symbol: Some('$'),
+            indestructible: true,
+            ..room_type::CROSSING
+        };
+        let mut game = GameState::new(3, 1, 0, 0, room_type::ENTRANCE);
+        game.update_room(0, 0, 1, Some(room_type::HALL.rotate_right()));
+        game.update_room(0, 0, 2, Some(vault));
+        game.resources = ENTRANCE_COST * 2;
+
+        game.add_entrance(0, 0, 1).unwrap();
+        game.add_entrance(0, 0, 2).unwrap();
+        assert_eq!(
+            game.get_map_level(0).rooms[(0, 2)].unwrap().symbol,
+            Some(ENTRANCE_SYMBOL)
+        );
+        // entrances remember their rooms wherever expanding moves them
+        game.expand(Direction::Left, 1);
+        game.remove_entrance(0, 0, 3).unwrap();
+        game.remove_entrance(0, 0, 2).unwrap();
+        assert_eq!(game.get_map_level(0).rooms[(0, 3)], Some(vault));
+        assert_eq!(
+            game.get_map_level(0).rooms[(0, 2)],
+            Some(room_type::HALL.rotate_right())
+        );
+        assert!(game.entrance_originals.is_empty());
+    }
+
+    #[test]
+    fn test_broken_entrance_recovers() {
+        let mut game = GameState::new(3, 1, 0, 0, room_type::CROSSING);
+        game.update_room(0, 0, 1, Some(room_type::CROSSING));
+        assert!(game.traversal_errors.is_empty());
+
+        // an entrance with nothing in it reaches nothing but doesn't stop the game
+        game.update_room(0, 0, 0, None);
+        assert_eq!(
+            game.traversal_errors,
+            vec![TraversalError::EmptyStart {
+                floor: 0,
+                row: 0,
+                col: 0
+            }]
+        );
+        assert!(depths(&game).is_empty());
+        game.advance(100);
+
+        game.update_room(0, 0, 0, Some(room_type::CROSSING));
+        assert!(game.traversal_errors.is_empty());
+        assert_eq!(depths(&game).len(), 2);
+    }
+
+    #[test]
+    fn test_incremental_traversal_matches_full() {
+        let mut rng = SimRng::new(0xd1ce);
+        let random_room = |rng: &mut SimRng| {
+            let mut room =
+                room_type::ALL_TYPES[rng.below(room_type::ALL_TYPES.len() as u32) as usize];
+            for _ in 0..rng.below(4) {
+                room = room.rotate_right();
+            }
+            room.traversal_cost = rng.below(4);
+            return room;
+        };
+        for case in 0..200 {
+            let (width, height) = (1 + rng.below(5) as usize, 1 + rng.below(5) as usize);
+            let mut dungeon = Dungeon::new(width, height);
+            for _ in 0..rng.below(3) {
+                dungeon.add_floor();
+            }
+            // entrances may end up empty, which has to be reported the same way too
+            let entrances: Vec<(usize, usize)> = (0..1 + rng.below(2))
+                .map(|_| {
+                    (
+                        rng.below(height as u32) as usize,
+                        rng.below(width as u32) as usize,
+                    )
+                })
+                .collect();
+            let mut game = GameState::from_dungeon(dungeon, entrances.clone());
+            if rng.below(2) == 1 {
+                game.set_traversal_mode(TraversalMode::Weighted);
+            }
+
+            for edit in 0..40 {
+                let floor = rng.below(game.floor_count() as u32) as usize;
+                let (row, col) = (
+                    rng.below(height as u32) as usize,
+                    rng.below(width as u32) as usize,
+                );
+                // mostly building, so the dungeon fills up enough to have loops and stairs
+                let room = if rng.below(8) == 0 {
+                    None
+                } else {
+                    Some(random_room(&mut rng))
+                };
+                game.update_room(floor, row, col, room);
+
+                let mut full = GameState::from_dungeon(game.dungeon.clone(), entrances.clone());
+                full.set_traversal_mode(game.traversal_mode);
+                assert_eq!(
+                    game.traversal_info, full.traversal_info,
+                    "case {case}, edit {edit}: {:?} at ({floor}, {row}, {col})",
+                    room
+                );
+                assert_eq!(game.traversal_errors, full.traversal_errors);
+            }
+        }
+    }
+
+    #[test]
+    fn test_weighted_traversal_mode() {
+        // two ways around a loop from the entrance in the top left, the top one is shorter but has a trap in it
+        let mut level = MapLevel::from_ascii(
+            "
++-++-++-+
+|       |
++ ++-++ +
++ ++-++ +
+|       |
++-++-++-+
+",
+            &RoomCatalogue::built_in(),
+        )
+        .unwrap();
+        level.rooms[(0, 1)].as_mut().unwrap().traversal_cost = 10;
+        let mut game = GameState::from_dungeon(
+            Dungeon {
+                floors: vec![level],
+            },
+            vec![(0, 0)],
+        );
+        assert_eq!(game.traversal_info[0][(0, 2)].unwrap().depth, 2);
+
+        game.set_traversal_mode(TraversalMode::Weighted);
+        let far_corner = game.traversal_info[0][(0, 2)].unwrap();
+        assert_eq!((far_corner.depth, far_corner.cost), (4, 5));
+
+        game.set_traversal_mode(TraversalMode::Breadth);
+        assert_eq!(game.traversal_info[0][(0, 2)].unwrap().depth, 2);
+    }
+
+    #[test]
+    fn test_income_from_reachable_rooms() {
+        let mut game = GameState::new(3, 2, 1, 1, room_type::ENTRANCE);
+        game.update_room(0, 1, 0, Some(room_type::DEAD_END.rotate_right()));
+        game.update_room(0, 0, 1, Some(room_type::HALL));
+        // not connected to anything
+        game.update_room(0, 0, 0, Some(room_type::CROSSING));
+        game.add_floor();
+        game.update_room(0, 1, 2, Some(room_type::STAIRS_DOWN.rotate_left()));
+        game.update_room(1, 1, 2, Some(room_type::STAIRS_UP));
+
+        // depth 0 entrance, three rooms at depth 1 and the bottom of the stairs at depth 2 on the second floor
+        assert_eq!(
+            game.income(),
+            Resources {
+                gold: 1 + 2 * 3 + 3,
+                mana: 1,
+                souls: 0
+            }
+        );
+
+        for _ in 1..INCOME_INTERVAL_TICKS {
+            game.tick();
+        }
+        assert_eq!(game.resources, STARTING_RESOURCES);
+        game.tick();
+        assert_eq!(game.resources, STARTING_RESOURCES + game.income());
+    }
+
+    #[test]
+    fn test_income_includes_production() {
+        let mut game = GameState::new(3, 1, 0, 1, room_type::ENTRANCE);
+        let base = game.income();
+        let treasury = SimpleRoomDrawInfo {
+            production: Resources {
+                gold: 5,
+                mana: 0,
+                souls: 1,
+            },
+            ..room_type::DEAD_END
+        };
+        // facing away from the entrance, so it can't be reached and produces nothing
+        game.update_room(0, 0, 0, Some(treasury));
+        assert_eq!(game.income(), base);
+        game.update_room(0, 0, 0, Some(treasury.rotate_right()));
+        assert_eq!(
+            game.income(),
+            base + Resources {
+                gold: 2 + 5,
+                mana: 0,
+                souls: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_buy_expansion() {
+        let mut game = GameState::new(2, 2, 1, 1, room_type::ENTRANCE);
+        game.resources = EXPANSION_COST_PER_CELL * 3;
+
+        assert_eq!(
+            game.expansion_cost(Direction::Right, 1),
+            EXPANSION_COST_PER_CELL * 2
+        );
+        assert!(game.buy_expansion(Direction::Right, 1));
+        assert_eq!(game.resources, EXPANSION_COST_PER_CELL);
+        assert_eq!(game.get_map_level(0).width(), 3);
+
+        assert!(!game.buy_expansion(Direction::Top, 1));
+        assert_eq!(game.resources, EXPANSION_COST_PER_CELL);
+        assert_eq!(game.get_map_level(0).height(), 2);
+    }
+
+    #[test]
+    fn test_buy_floor() {
+        let mut game = GameState::new(2, 3, 1, 1, room_type::ENTRANCE);
+        game.resources = EXPANSION_COST_PER_CELL * 10;
+        assert_eq!(game.floor_cost(), EXPANSION_COST_PER_CELL * 6);
+        assert!(game.buy_floor());
+        assert_eq!(game.floor_count(), 2);
+        assert_eq!(game.resources, EXPANSION_COST_PER_CELL * 4);
+
+        assert!(!game.buy_floor());
+        assert_eq!(game.floor_count(), 2);
+        assert_eq!(game.resources, EXPANSION_COST_PER_CELL * 4);
+        // every floor has to grow, so expanding costs more with each one dug
+        assert_eq!(
+            game.expansion_cost(Direction::Right, 1),
+            EXPANSION_COST_PER_CELL * 6
+        );
+    }
+
+    #[test]
+    fn test_build_costs_and_refunds() {
+        let mut game = GameState::new(3, 2, 1, 1, room_type::ENTRANCE);
+        game.resources = Resources::gold(50);
+        game.build_policy.refund_percent = 50;
+
+        assert_eq!(game.build_room(0, 0, 1, Some(room_type::CROSSING)), Ok(()));
+        assert_eq!(game.resources, Resources::gold(10));
+
+        let refused = game.build_room(0, 0, 0, Some(room_type::HALL));
+        assert_eq!(
+            refused,
+            Err(EditError::CantAfford {
+                cost: room_type::HALL.cost
+            })
+        );
+        assert_eq!(game.resources, Resources::gold(10));
+        assert_eq!(game.get_map_level(0).rooms[(0, 0)], None);
+
+        // replacing pays for the new room out of the refund for the old one
+        assert_eq!(game.build_room(0, 0, 1, Some(room_type::HALL)), Ok(()));
+        assert_eq!(game.resources, Resources::gold(10 + 20 - 15));
+
+        assert_eq!(game.build_room(0, 0, 1, None), Ok(()));
+        assert_eq!(game.resources, Resources::gold(15 + 7));
+        assert_eq!(game.get_map_level(0).rooms[(0, 1)], None);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut game = GameState::new(3, 2, 1, 1, room_type::ENTRANCE);
+        game.resources = Resources::gold(100);
+        let fresh_dungeon = game.dungeon.clone();
+        let fresh_traversal = game.traversal_info.clone();
+        assert_eq!(game.undo(), Err(EditError::NothingToUndo));
+
+        game.build_room(0, 0, 1, Some(room_type::CROSSING)).unwrap();
+        game.rotate_room(0, 0, 1, true).unwrap();
+        game.build_room(0, 0, 1, Some(room_type::HALL)).unwrap();
+        game.build_room(0, 1, 0, Some(room_type::L)).unwrap();
+        let edited_dungeon = game.dungeon.clone();
+        let edited_traversal = game.traversal_info.clone();
+        let edited_resources = game.resources;
+
+        for _ in 0..4 {
+            game.undo().unwrap();
+        }
+        assert_eq!(game.undo(), Err(EditError::NothingToUndo));
+        assert_eq!(game.dungeon, fresh_dungeon);
+        assert_eq!(game.traversal_info, fresh_traversal);
+        assert_eq!(game.resources, Resources::gold(100));
+
+        for _ in 0..4 {
+            game.redo().unwrap();
+        }
+        assert_eq!(game.redo(), Err(EditError::NothingToRedo));
+        assert_eq!(game.dungeon, edited_dungeon);
+        assert_eq!(game.traversal_info, edited_traversal);
+        assert_eq!(game.resources, edited_resources);
+
+        // a new edit can't be followed by redoing older ones
+        game.undo().unwrap();
+        game.build_room(0, 1, 2, Some(room_type::DEAD_END)).unwrap();
+        assert_eq!(game.redo(), Err(EditError::NothingToRedo));
+    }
+
+    #[test]
+    fn test_clear_area_and_protection() {
+        let mut game = GameState::new(3, 2, 1, 1, room_type::ENTRANCE);
+        game.resources = Resources::gold(1000);
+        for (row, col) in [(0, 0), (0, 1), (0, 2), (1, 0)] {
+            game.build_room(0, row, col, Some(room_type::CROSSING))
+                .unwrap();
+        }
+        let built = game.dungeon.clone();
+        let resources = game.resources;
+
+        assert_eq!(
+            game.build_room(0, 1, 1, None),
+            Err(EditError::Indestructible)
+        );
+        assert_eq!(
+            game.build_room(0, 1, 1, Some(room_type::HALL)),
+            Err(EditError::Indestructible)
+        );
+        // rotating doesn't remove anything
+        assert_eq!(game.rotate_room(0, 1, 1, true), Ok(()));
+        game.undo().unwrap();
+
+        // the entrance is in the middle of the area but stays
+        assert_eq!(game.clear_area(0, (1, 2), (0, 0)), 4);
+        assert_eq!(
+            game.resources,
+            resources + room_type::CROSSING.cost.percent(50) * 4
+        );
+        let level = game.get_map_level(0);
+        assert_eq!(level.rooms.iter_some().count(), 1);
+        assert_eq!(level.rooms[(1, 1)], Some(room_type::ENTRANCE));
+        assert_eq!(game.clear_area(0, (0, 0), (1, 2)), 0);
+
+        // one undo puts the whole area back
+        game.undo().unwrap();
+        assert_eq!(game.dungeon, built);
+        assert_eq!(game.resources, resources);
+
+        // an entrance that is closed again is an ordinary room
+        game.add_entrance(0, 0, 1).unwrap();
+        assert_eq!(
+            game.build_room(0, 0, 1, None),
+            Err(EditError::Indestructible)
+        );
+        game.remove_entrance(0, 0, 1).unwrap();
+        assert_eq!(game.build_room(0, 0, 1, None), Ok(()));
+    }
+
+    #[test]
+    fn test_undo_limits() {
+        let mut game = GameState::new(3, 2, 1, 1, room_type::ENTRANCE);
+        game.resources = Resources::gold(40);
+        game.build_room(0, 0, 1, Some(room_type::CROSSING)).unwrap();
+        game.build_room(0, 0, 1, None).unwrap();
+        // the refund for removing the crossing is gone, so it can't be taken back
+        game.resources = Resources::ZERO;
+        assert_eq!(
+            game.undo(),
+            Err(EditError::CantAfford {
+                cost: room_type::CROSSING.cost.percent(50)
+            })
+        );
+        assert_eq!(game.get_map_level(0).rooms[(0, 1)], None);
+        game.resources = Resources::gold(20);
+        assert_eq!(game.undo(), Ok(()));
+        assert_eq!(
+            game.get_map_level(0).rooms[(0, 1)],
+            Some(room_type::CROSSING)
+        );
+
+        // growing on the top keeps undo pointing at the same room
+        game.expand(Direction::Top, 1);
+        game.undo().unwrap();
+        assert_eq!(game.get_map_level(0).rooms[(1, 1)], None);
+        assert_eq!(game.resources, Resources::gold(40));
+
+        // only the latest MAX_HISTORY edits are remembered
+        game.resources = Resources::gold(1000);
+        for _ in 0..MAX_HISTORY + 5 {
+            game.rotate_room(0, 2, 1, true).unwrap();
+        }
+        for _ in 0..MAX_HISTORY {
+            game.undo().unwrap();
+        }
+        assert_eq!(game.undo(), Err(EditError::NothingToUndo));
+    }
+
+    #[test]
+    fn test_corridor_and_strokes() {
+        let mut game = GameState::new(4, 3, 0, 0, room_type::ENTRANCE);
+        game.resources = Resources::gold(1000);
+        let fresh_dungeon = game.dungeon.clone();
+        let exits = |game: &GameState, cell| {
+            let room = game.dungeon.floors[0].rooms[cell].unwrap();
+            return Direction::ALL.map(|x| room.has_exit(x));
+        };
+
+        // a corridor dragged right then down then back round to where it started
+        game.history.begin_step();
+        let path = [(0, 1), (0, 2), (1, 2), (1, 1), (0, 1)];
+        for cells in path.windows(2) {
+            game.extend_corridor(0, cells[0], cells[1], &room_type::ALL_TYPES)
+                .unwrap();
+        }
+        game.history.end_step();
+        assert_eq!(exits(&game, (0, 1)), [false, true, false, true]);
+        assert_eq!(exits(&game, (0, 2)), [true, false, false, true]);
+        assert_eq!(exits(&game, (1, 2)), [true, false, true, false]);
+        assert_eq!(exits(&game, (1, 1)), [false, true, true, false]);
+
+        // joining onto the entrance leaves it alone
+        game.extend_corridor(0, (1, 1), (1, 0), &room_type::ALL_TYPES)
+            .unwrap();
+        game.extend_corridor(0, (1, 0), (0, 0), &room_type::ALL_TYPES)
+            .unwrap();
+        assert_eq!(exits(&game, (1, 1)), [true, true, true, false]);
+        assert_eq!(exits(&game, (1, 0)), [false, true, true, false]);
+        assert_eq!(
+            game.dungeon.floors[0].rooms[(0, 0)].unwrap().symbol,
+            Some('E')
+        );
+
+        // outside a stroke each corridor is its own step, then the whole drag is undone at once
+        for _ in 0..3 {
+            game.undo().unwrap();
+        }
+        assert_eq!(game.dungeon, fresh_dungeon);
+        assert_eq!(game.resources, Resources::gold(1000));
+
+        // a stroke still open when undoing is finished first
+        game.history.begin_step();
+        game.build_room(0, 2, 0, Some(room_type::HALL)).unwrap();
+        game.build_room(0, 2, 1, Some(room_type::HALL)).unwrap();
+        game.undo().unwrap();
+        assert_eq!(game.dungeon, fresh_dungeon);
+        assert_eq!(game.undo(), Err(EditError::NothingToUndo));
+    }
+
+    #[test]
+    fn test_corridor_failures() {
+        let mut game = GameState::new(4, 3, 0, 0, room_type::ENTRANCE);
+        game.resources = Resources::gold(1000);
+        game.build_room(0, 1, 1, Some(room_type::HALL.rotate_left()))
+            .unwrap();
+        let (before, resources) = (game.dungeon.clone(), game.resources);
+
+        assert_eq!(
+            game.extend_corridor(0, (0, 1), (1, 2), &room_type::ALL_TYPES),
+            Err(EditError::NotNextToEachOther)
+        );
+
+        // rooms with a symbol or production are never built as corridor
+        let special = [
+            SimpleRoomDrawInfo {
+                symbol: Some('$'),
+                ..room_type::DEAD_END
+            },
+            SimpleRoomDrawInfo {
+                production: Resources::gold(1),
+                ..room_type::DEAD_END
+            },
+        ];
+        assert_eq!(
+            game.extend_corridor(0, (0, 1), (0, 2), &special),
+            Err(EditError::NoCorridorRoom)
+        );
+
+        // the empty cell could take a dead end but the hall has nothing to turn into, so neither is built
+        assert_eq!(
+            game.extend_corridor(0, (0, 1), (1, 1), &[room_type::DEAD_END]),
+            Err(EditError::NoCorridorRoom)
+        );
+        assert_eq!(game.dungeon, before);
+        assert_eq!(game.resources, resources);
+    }
+
+    #[test]
+    fn test_rotation_cost() {
+        let mut game = GameState::new(3, 2, 1, 1, room_type::ENTRANCE);
+        game.update_room(0, 0, 1, Some(room_type::DEAD_END));
+        game.resources = Resources::gold(1);
+        game.build_policy.rotation_cost = Resources::gold(1);
+
+        assert_eq!(game.rotate_room(0, 0, 1, true), Ok(()));
+        assert_eq!(
+            game.get_map_level(0).rooms[(0, 1)],
+            Some(room_type::DEAD_END.rotate_right())
+        );
+        assert_eq!(
+            game.rotate_room(0, 0, 1, false),
+            Err(EditError::CantAfford {
+                cost: Resources::gold(1)
+            })
+        );
+        assert_eq!(game.rotate_room(0, 0, 0, false), Err(EditError::NoRoom));
+
+        game.build_policy.rotation_cost = Resources::ZERO;
+        assert_eq!(game.rotate_room(0, 0, 1, false), Ok(()));
+        assert_eq!(
+            game.get_map_level(0).rooms[(0, 1)],
+            Some(room_type::DEAD_END)
+        );
+    }
+
+    #[test]
+    fn test_catch_up_offline_progress() {
+        let mut game = GameState::new(3, 2, 1, 1, room_type::ENTRANCE);
+        game.update_room(0, 1, 0, Some(room_type::DEAD_END.rotate_right()));
+        game.update_room(0, 0, 1, Some(room_type::HALL));
+        game.last_seen_unix_secs = 1_000;
+        let income = game.income();
+
+        assert_eq!(game.catch_up(1_010), None, "short absences have no summary");
+        assert_eq!(game.resources, STARTING_RESOURCES + income * 10);
+
+        let summary = game.catch_up(1_010 + 3_600).unwrap();
+        assert_eq!(summary.seconds_away, 3_600);
+        assert_eq!(summary.seconds_simulated, 3_600);
+        assert_eq!(summary.earned.gold, income.gold * 3_600);
+        assert_eq!(summary.earned.souls, summary.adventurers.died as u64);
+        assert_eq!(
+            summary.adventurers.spawned,
+            summary.adventurers.left + summary.adventurers.died
+        );
+        assert!(summary.adventurers.spawned > 0);
+        assert!(game.adventurers.active.is_empty());
+        assert_eq!(game.last_seen_unix_secs, 1_010 + 3_600);
+
+        let capped = game
+            .catch_up(1_010 + 3_600 + MAX_OFFLINE_SECONDS * 3)
+            .unwrap();
+        assert_eq!(capped.seconds_away, MAX_OFFLINE_SECONDS * 3);
+        assert_eq!(capped.seconds_simulated, MAX_OFFLINE_SECONDS);
+    }
+
+    #[test]
+    fn test_advance_matches_ticking_income() {
+        let mut ticked = GameState::new(3, 2, 1, 1, room_type::ENTRANCE);
+        ticked.update_room(0, 0, 1, Some(room_type::HALL));
+        let mut advanced = GameState::new(3, 2, 1, 1, room_type::ENTRANCE);
+        advanced.update_room(0, 0, 1, Some(room_type::HALL));
+
+        for _ in 0..TICKS_PER_SECOND * 7 + 3 {
+            ticked.tick();
+        }
+        advanced.advance(TICKS_PER_SECOND * 7 + 3);
+
+        assert_eq!(ticked.resources.gold, advanced.resources.gold);
+        assert_eq!(ticked.income_timer, advanced.income_timer);
+        assert_eq!(
+            ticked.adventurers.spawn_timer,
+            advanced.adventurers.spawn_timer
+        );
+    }
+}
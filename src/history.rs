@@ -0,0 +1,107 @@
+use std::collections::VecDeque;
+
+use crate::{
+    map::{Cell, Direction, room::SimpleRoomDrawInfo},
+    resources::Resources,
+};
+
+// oldest steps are forgotten past this many
+pub const MAX_HISTORY: usize = 100;
+
+/// A change to one cell of the dungeon, with what it cost so it can be paid back on undo
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RoomEdit {
+    pub cell: Cell,
+    pub before: Option<SimpleRoomDrawInfo>,
+    pub after: Option<SimpleRoomDrawInfo>,
+    pub spent: Resources,
+    pub refunded: Resources,
+}
+
+/// Everything one player action changed, undone and redone together. Edits are in the order they were made
+pub type Step = Vec<RoomEdit>;
+
+/// The steps that can be undone, most recent last, and the undone ones that can be redone
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct History {
+    undo: VecDeque<Step>,
+    redo: Vec<Step>,
+    // edits made since [History::begin_step], recorded as a single step once it ends
+    pending: Option<Step>,
+}
+
+impl History {
+    /// Remembers a new step, which makes anything undone before it impossible to redo. Empty steps are ignored
+    pub fn record(&mut self, mut step: Step) {
+        if let Some(pending) = self.pending.as_mut() {
+            pending.append(&mut step);
+            return;
+        }
+        if step.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push_back(step);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Groups everything recorded from now until [History::end_step] into one step, for actions like dragging that
+    /// edit many cells over many frames
+    pub fn begin_step(&mut self) {
+        self.end_step();
+        self.pending = Some(Vec::new());
+    }
+
+    pub fn end_step(&mut self) {
+        if let Some(step) = self.pending.take() {
+            self.record(step);
+        }
+    }
+
+    pub fn next_undo(&self) -> Option<&Step> {
+        return self.undo.back();
+    }
+
+    pub fn next_redo(&self) -> Option<&Step> {
+        return self.redo.last();
+    }
+
+    /// Moves the step returned by [History::next_undo] over to the redo side
+    pub fn undone(&mut self) {
+        if let Some(step) = self.undo.pop_back() {
+            self.redo.push(step);
+        }
+    }
+
+    /// Moves the step returned by [History::next_redo] back to the undo side
+    pub fn redone(&mut self) {
+        if let Some(step) = self.redo.pop() {
+            self.undo.push_back(step);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.pending = None;
+    }
+
+    /// Keeps every remembered cell pointing at the same room when the dungeon grows on the top or left
+    pub fn expand(&mut self, side: Direction, count: usize) {
+        for edit in self
+            .undo
+            .iter_mut()
+            .chain(self.redo.iter_mut())
+            .chain(self.pending.iter_mut())
+            .flat_map(|x| x.iter_mut())
+        {
+            match side {
+                Direction::Top => edit.cell.1 += count,
+                Direction::Left => edit.cell.2 += count,
+                Direction::Bottom | Direction::Right => {}
+            }
+        }
+    }
+}
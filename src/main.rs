@@ -1,64 +1,159 @@
+// every function in this crate ends with an explicit `return`, that is the house style rather than a mistake
+#![allow(clippy::needless_return)]
+
+use std::path::Path;
+
 use macroquad::prelude::*;
 
-use crate::map::{TraversalInfo, room::SimpleRoomDrawInfo};
+use crate::{
+    camera::Camera,
+    game::GameState,
+    map::{Direction, grid},
+    palette::{Palette, Tool},
+};
+mod adventurer;
+mod camera;
+mod game;
+mod history;
 mod map;
+mod palette;
+mod resources;
+mod save;
 
 const MAP_WIDTH: usize = 5;
 const MAP_HEIGHT: usize = 5;
 
-type MapInfo<T> = [[Option<T>; MAP_WIDTH]; MAP_HEIGHT];
-type RoomsInfo = MapInfo<SimpleRoomDrawInfo>;
+const AUTOSAVE_SECONDS: f64 = 30.0;
+
+// how long a refused edit's message stays on screen
+const FEEDBACK_SECONDS: f64 = 2.0;
 
-struct GameState {
-    rooms: RoomsInfo,
-    traversal_info: MapInfo<TraversalInfo>,
-    entrance_rowcols: Vec<(usize, usize)>,
+// picks the tool at the same position in Tool::ALL
+const TOOL_KEYS: [KeyCode; 4] = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+
+const EXPAND_KEYS: [(KeyCode, Direction); 4] = [
+    (KeyCode::Left, Direction::Left),
+    (KeyCode::Right, Direction::Right),
+    (KeyCode::Up, Direction::Top),
+    (KeyCode::Down, Direction::Bottom),
+];
+
+fn draw_away_summary(summary: &game::OfflineSummary) {
+    let (width, height) = (screen_width() * 0.6, 200.0);
+    let (x, y) = (
+        (screen_width() - width) / 2.0,
+        (screen_height() - height) / 2.0,
+    );
+    draw_rectangle(x, y, width, height, DARKGRAY.with_alpha(0.9));
+    draw_rectangle_lines(x, y, width, height, 4.0, GOLD);
+
+    let mut lines = vec![
+        "While you were away...".to_string(),
+        format!(
+            "{}h {}m passed",
+            summary.seconds_away / 3600,
+            summary.seconds_away % 3600 / 60
+        ),
+    ];
+    if summary.seconds_simulated < summary.seconds_away {
+        lines.push(format!(
+            "(only the first {}h counted)",
+            summary.seconds_simulated / 3600
+        ));
+    }
+    lines.push(format!("earned {}", summary.earned));
+    lines.push(format!(
+        "{} adventurers visited, {} never left",
+        summary.adventurers.spawned, summary.adventurers.died
+    ));
+    lines.push("click or press any key to continue".to_string());
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(line, x + 20.0, y + 35.0 + i as f32 * 28.0, 26.0, WHITE);
+    }
 }
 
-impl GameState {
-    pub fn new(
-        initial_entrance_row: usize,
-        initial_entrance_col: usize,
-        initial_room: SimpleRoomDrawInfo,
-    ) -> Self {
-        let mut game = GameState {
-            rooms: [[None; MAP_WIDTH]; MAP_HEIGHT],
-            traversal_info: [[None; MAP_WIDTH]; MAP_HEIGHT],
-            entrance_rowcols: Vec::new(),
-        };
-        game.rooms[initial_entrance_row][initial_entrance_col] = Some(initial_room);
-        game.traversal_info[initial_entrance_row][initial_entrance_col] = Some(TraversalInfo {
-            depth: 0,
-            row: initial_entrance_row as isize,
-            col: initial_entrance_col as isize,
-            room_info: initial_room,
-        });
-        game.entrance_rowcols
-            .push((initial_entrance_row, initial_entrance_col));
-        return game;
+/// Joins the middle of every room along the route, with a dot where it ends
+fn draw_route(route: &map::path::Path, map_top_left: Vec2, map_scale: f32) {
+    let centre = |step: &map::path::PathStep| {
+        map_top_left + Vec2::new(step.col as f32 + 0.5, step.row as f32 + 0.5) * map_scale
+    };
+    for pair in route.steps.windows(2) {
+        let (from, to) = (centre(&pair[0]), centre(&pair[1]));
+        draw_line(from.x, from.y, to.x, to.y, 6.0, SKYBLUE);
     }
+    if let Some(last) = route.steps.last() {
+        let end = centre(last);
+        draw_circle(end.x, end.y, map_scale / 8.0, SKYBLUE);
+    }
+}
 
-    fn update_room(&mut self, row: usize, col: usize, new_room: Option<SimpleRoomDrawInfo>) {
-        self.rooms[row][col] = new_room;
-        // clear the traversal map
-        self.traversal_info = [[None; MAP_WIDTH]; MAP_HEIGHT];
-        // recalculate depths based on every entrance (only one should exist as of 2025-10-21 anyway), taking the lowest depth when two entrances can reach the same location
-        for (e_row, e_col) in self.entrance_rowcols.iter() {
-            self.get_map_level().breadth_traverse(*e_row, *e_col, |ti| {
-                let info_slot = &mut self.traversal_info[ti.row as usize][ti.col as usize];
-                *info_slot = Some(info_slot.map_or(ti, |existing| {
-                    if existing.depth < ti.depth {
-                        existing
-                    } else {
-                        ti
-                    }
-                }))
-            });
+/// Marks every problem on the floor being shown and lists the problems on all floors down the right hand side
+fn draw_diagnostics(
+    diagnostics: &map::diagnostics::Diagnostics,
+    floor: usize,
+    map_top_left: Vec2,
+    map_scale: f32,
+) {
+    let centre = |row: usize, col: usize| {
+        map_top_left + Vec2::new(col as f32 + 0.5, row as f32 + 0.5) * map_scale
+    };
+    let mut lines = Vec::new();
+    for (room_floor, row, col) in diagnostics.unreachable.iter().copied() {
+        if room_floor == floor {
+            let top_left = centre(row, col) - Vec2::splat(map_scale / 2.0);
+            draw_rectangle(
+                top_left.x,
+                top_left.y,
+                map_scale,
+                map_scale,
+                RED.with_alpha(0.4),
+            );
+        }
+        lines.push(format!(
+            "floor {} ({row}, {col}) can't be reached",
+            room_floor + 1
+        ));
+    }
+    for ((room_floor, row, col), side, problem) in diagnostics.exits.iter().copied() {
+        if room_floor == floor {
+            let (row_add, col_add) = side.offset();
+            let marker =
+                centre(row, col) + Vec2::new(col_add as f32, row_add as f32) * map_scale * 0.4;
+            draw_circle(marker.x, marker.y, map_scale / 10.0, ORANGE);
         }
+        lines.push(format!(
+            "floor {} ({row}, {col}) {side:?} exit {problem}",
+            room_floor + 1
+        ));
+    }
+    for cycle in diagnostics.cycles.iter() {
+        for (from, to) in cycle.iter().zip(cycle.iter().cycle().skip(1)) {
+            if from.0 == floor && to.0 == floor {
+                let (from, to) = (centre(from.1, from.2), centre(to.1, to.2));
+                draw_line(from.x, from.y, to.x, to.y, 4.0, VIOLET);
+            }
+        }
+        let (start_floor, row, col) = cycle[0];
+        lines.push(format!(
+            "loop of {} rooms through floor {} ({row}, {col})",
+            cycle.len(),
+            start_floor + 1
+        ));
     }
 
-    fn get_map_level(&self) -> map::MapLevel<MAP_WIDTH, MAP_HEIGHT> {
-        return map::MapLevel { rooms: self.rooms };
+    // only as many as fit on screen
+    let max_lines = ((screen_height() - 80.0) / 20.0).max(1.0) as usize;
+    if lines.len() > max_lines {
+        let hidden = lines.len() - max_lines + 1;
+        lines.truncate(max_lines - 1);
+        lines.push(format!("...and {hidden} more"));
+    }
+    if lines.is_empty() {
+        lines.push("no problems found".to_string());
+    }
+    let x = screen_width() - 420.0;
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(line, x, 40.0 + i as f32 * 20.0, 20.0, BLACK);
     }
 }
 
@@ -67,48 +162,249 @@ async fn main() {
     let entrance_row = MAP_HEIGHT - 1;
     let entrance_col = MAP_WIDTH / 2;
 
-    let mut game = GameState::new(
-        entrance_row,
-        entrance_col,
-        map::room::SimpleRoomDrawInfo {
-            top_exit: true,
-            right_exit: true,
-            left_exit: true,
-            bottom_exit: false,
-            symbol: Some('E'),
-        },
+    let new_game = || {
+        GameState::new(
+            MAP_WIDTH,
+            MAP_HEIGHT,
+            entrance_row,
+            entrance_col,
+            map::room::SimpleRoomDrawInfo {
+                top_exit: true,
+                right_exit: true,
+                left_exit: true,
+                bottom_exit: false,
+                symbol: Some(game::ENTRANCE_SYMBOL),
+                stairs: None,
+                cost: resources::Resources::ZERO,
+                traversal_cost: 1,
+                colour: map::room::ROOM_BODY,
+                production: resources::Resources::ZERO,
+                indestructible: true,
+            },
+        )
+    };
+    // message explaining a refused edit or a save problem, and when it was shown
+    let mut feedback: Option<(String, f64)> = None;
+    let save_path = Path::new(save::SAVE_PATH);
+    let mut game = match save::load_from_file(save_path) {
+        Ok(Some(game)) => game,
+        Ok(None) => new_game(),
+        Err(e) => {
+            // keep the unreadable save around rather than overwriting it with the next autosave
+            let moved_to = save_path.with_extension("unreadable");
+            let moved = std::fs::rename(save_path, &moved_to).is_ok();
+            feedback = Some((
+                if moved {
+                    format!("{e} (moved to {})", moved_to.display())
+                } else {
+                    e.to_string()
+                },
+                get_time(),
+            ));
+            new_game()
+        }
+    };
+    let catalogue =
+        match map::catalogue::RoomCatalogue::load(Path::new(map::catalogue::CATALOGUE_PATH)) {
+            Ok(catalogue) => catalogue,
+            Err(e) => {
+                // a broken catalogue shouldn't stop the game, the player still gets the rooms it ships with
+                feedback = Some((e.to_string(), get_time()));
+                map::catalogue::RoomCatalogue::built_in()
+            }
+        };
+    let mut palette = Palette::default();
+    // where the clear area tool's rectangle was started
+    let mut area_start: Option<(usize, usize)> = None;
+    // the last cell the paint or corridor tool reached while the button has been held
+    let mut stroke_last: Option<(usize, usize)> = None;
+    // what the corridor tool picks from
+    let corridor_rooms: Vec<_> = catalogue.rooms.iter().map(|x| x.room).collect();
+    let mut current_floor: usize = 0;
+    let mut show_diagnostics = false;
+    let mut show_heatmap = false;
+    let top = game.get_map_level(0);
+    let mut camera = Camera::fit(
+        top.width(),
+        top.height(),
+        Vec2::new(screen_width(), screen_height()),
     );
-    let mut current_creating_room_type: usize = 0;
+    let mut away_summary = game.catch_up(game::now_unix_secs());
+    let mut last_autosave = get_time();
+    prevent_quit();
     loop {
-        let map = game.get_map_level();
-        clear_background(LIGHTGRAY);
+        game.update(get_frame_time());
+        game.last_seen_unix_secs = game::now_unix_secs();
 
-        let map_scale = screen_width() / 10.0;
+        if is_key_released(KeyCode::PageUp) {
+            current_floor = current_floor.saturating_sub(1);
+        }
+        if is_key_released(KeyCode::PageDown) {
+            current_floor = (current_floor + 1).min(game.floor_count() - 1);
+        }
+        // G digs a new floor below the deepest one and goes down to it
+        if is_key_released(KeyCode::G) {
+            let cost = game.floor_cost();
+            if game.buy_floor() {
+                current_floor = game.floor_count() - 1;
+            } else {
+                feedback = Some((format!("can't afford {cost}"), get_time()));
+            }
+        }
+
+        for (key, side) in EXPAND_KEYS {
+            if !is_key_released(key) {
+                continue;
+            }
+            if game.buy_expansion(side, 1) {
+                // growing towards the top or left moves everything already built, the view follows it
+                match side {
+                    Direction::Top => camera.centre.y += 1.0,
+                    Direction::Left => camera.centre.x += 1.0,
+                    Direction::Bottom | Direction::Right => {}
+                }
+            } else {
+                feedback = Some((
+                    format!("can't afford {}", game.expansion_cost(side, 1)),
+                    get_time(),
+                ));
+            }
+        }
 
-        map::room::room_type::ALL_TYPES[current_creating_room_type]
-            .draw(Vec2 { x: 20.0, y: 20.0 }, map_scale);
-        draw_rectangle_lines(20.0, 20.0, map_scale, map_scale, 10.0, BLUE);
-        if is_key_released(KeyCode::D) {
-            current_creating_room_type =
-                (current_creating_room_type + 1) % map::room::room_type::ALL_TYPES.len();
+        if is_key_released(KeyCode::T) {
+            let (mode, name) = match game.traversal_mode {
+                map::TraversalMode::Breadth => (map::TraversalMode::Weighted, "traversal cost"),
+                map::TraversalMode::Weighted => (map::TraversalMode::Breadth, "room count"),
+            };
+            game.set_traversal_mode(mode);
+            feedback = Some((format!("depths now measured by {name}"), get_time()));
         }
 
-        let coords = map.draw(
-            Vec2::new(
-                screen_width() / 2.0 - map_scale * MAP_WIDTH as f32 / 2.0,
-                screen_height() / 2.0 - map_scale * MAP_HEIGHT as f32 / 2.0,
+        // ctrl+Z undoes the last room edit, ctrl+Y or ctrl+shift+Z puts it back
+        let ctrl_down = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+        if ctrl_down && away_summary.is_none() {
+            let shift_down = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+            let edit_result =
+                if is_key_pressed(KeyCode::Y) || (shift_down && is_key_pressed(KeyCode::Z)) {
+                    game.redo()
+                } else if is_key_pressed(KeyCode::Z) {
+                    game.undo()
+                } else {
+                    Ok(())
+                };
+            if let Err(e) = edit_result {
+                feedback = Some((e.to_string(), get_time()));
+            }
+        }
+
+        let map = game.get_map_level(current_floor);
+        clear_background(LIGHTGRAY);
+
+        if ctrl_down && is_key_pressed(KeyCode::C) {
+            // plain text layout for bug reports
+            miniquad::window::clipboard_set(&map.to_ascii());
+            feedback = Some((
+                format!("copied floor {} to the clipboard", current_floor + 1),
+                get_time(),
+            ));
+        }
+        if away_summary.is_none() {
+            camera.handle_input(map.width(), map.height());
+        }
+        let map_scale = camera.zoom;
+        let map_top_left = camera.map_top_left(Vec2::new(screen_width(), screen_height()));
+        // H tints rooms by depth
+        if is_key_released(KeyCode::H) {
+            show_heatmap = !show_heatmap;
+        }
+        let heatmap = show_heatmap.then(|| map::heatmap::Heatmap {
+            traversal_info: &game.traversal_info[current_floor],
+            max_depth: game.max_depth(),
+        });
+        let coords = map.draw(map_top_left, map_scale, heatmap.as_ref());
+        if let Some(heatmap) = heatmap.as_ref() {
+            heatmap.draw_legend(Vec2::new(20.0, screen_height() - 90.0), 200.0);
+        }
+        game.adventurers
+            .draw(current_floor, map_top_left, map_scale);
+        // V shows why rooms aren't connected
+        if is_key_released(KeyCode::V) {
+            show_diagnostics = !show_diagnostics;
+        }
+        if show_diagnostics {
+            let diagnostics = game.dungeon.diagnose(&game.entrance_rowcols);
+            draw_diagnostics(&diagnostics, current_floor, map_top_left, map_scale);
+        }
+
+        // the text stays the same size whatever the zoom, next to the palette
+        let hud_left = Palette::width(catalogue.rooms.len(), screen_height()) + 20.0;
+        let creating = &catalogue.rooms[palette.selected];
+        draw_text(game.resources.to_string(), hud_left, 40.0, 30.0, DARKBLUE);
+        draw_text(
+            format!("income: {}", game.income()),
+            hud_left,
+            70.0,
+            20.0,
+            DARKBLUE,
+        );
+        draw_text(
+            format!("building {}, cost: {}", creating.name, creating.room.cost),
+            hud_left,
+            100.0,
+            20.0,
+            DARKBLUE,
+        );
+        draw_text(
+            format!("floor {}/{}", current_floor + 1, game.floor_count()),
+            hud_left,
+            135.0,
+            30.0,
+            DARKBLUE,
+        );
+        draw_text(
+            format!(
+                "tool: {}, placing: {}",
+                palette.tool.name(),
+                palette.placement.name()
             ),
-            map_scale,
+            hud_left,
+            160.0,
+            20.0,
+            DARKBLUE,
         );
 
-        let (mouse_x, mouse_y) = mouse_position();
-        let bounds = coords.get_room(
-            &map,
-            Vec2 {
-                x: mouse_x,
-                y: mouse_y,
-            },
-        );
+        let mouse = Vec2::from(mouse_position());
+        let shift_down = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        if is_key_released(KeyCode::Tab) {
+            palette.select_next(&catalogue);
+        }
+        for (key, tool) in TOOL_KEYS.into_iter().zip(Tool::ALL) {
+            if is_key_released(key) {
+                palette.tool = tool;
+                area_start = None;
+                stroke_last = None;
+            }
+        }
+        // O switches between placing the brush as it is and fitting it to the rooms around it
+        if is_key_released(KeyCode::O) {
+            palette.placement = palette.placement.next();
+        }
+        // shift+E and shift+Q turn the room about to be placed, without shift they turn the one under the cursor
+        if shift_down && is_key_released(KeyCode::E) {
+            palette.rotate(true);
+        }
+        if shift_down && is_key_released(KeyCode::Q) {
+            palette.rotate(false);
+        }
+        let over_palette = Palette::contains(mouse, catalogue.rooms.len(), screen_height());
+        if away_summary.is_none() && is_mouse_button_released(MouseButton::Left) {
+            palette.handle_click(mouse, &catalogue);
+        }
+        palette.draw(&catalogue, mouse);
+
+        let bounds = coords.get_room(map, mouse);
+        // the away summary swallows clicks until it is dismissed, and the palette hides the map behind it
+        let bounds = bounds.filter(|_| away_summary.is_none() && !over_palette);
         bounds.inspect(|(room, found_bounds, (row, col))| {
             draw_rectangle_lines(
                 found_bounds.x,
@@ -118,9 +414,9 @@ async fn main() {
                 2.0,
                 GREEN,
             );
-            game.traversal_info[*row][*col].inspect(|ti| {
+            game.traversal_info[current_floor][(*row, *col)].inspect(|ti| {
                 draw_multiline_text(
-                    &ti.to_string(),
+                    ti.to_string(),
                     found_bounds.x + map_scale,
                     found_bounds.y + map_scale,
                     20.0,
@@ -128,19 +424,134 @@ async fn main() {
                     PURPLE,
                 );
             });
-            if is_mouse_button_released(MouseButton::Left)
-                && room.is_none_or(|r| r.symbol.is_none_or(|s| s != 'E'))
+            if is_key_down(KeyCode::R)
+                && let Some(route) = game.route_to_entrance(current_floor, *row, *col)
             {
-                let new_room = map::room::room_type::ALL_TYPES[current_creating_room_type].clone();
-                game.update_room(*row, *col, Some(new_room));
+                draw_route(&route, map_top_left, map_scale);
             }
-            if is_key_released(KeyCode::E) {
-                game.update_room(*row, *col, room.map(|r| r.rotate_right()));
+            let mut edit_result = Ok(());
+            match palette.tool {
+                Tool::Place => {
+                    if is_mouse_button_released(MouseButton::Left) {
+                        let level = game.get_map_level(current_floor);
+                        let new_room = palette.room_for(&catalogue, level, *row, *col);
+                        edit_result = game.build_room(current_floor, *row, *col, Some(new_room));
+                    }
+                }
+                Tool::ClearArea => {
+                    if is_mouse_button_pressed(MouseButton::Left) {
+                        area_start = Some((*row, *col));
+                    }
+                    if let Some(start) = area_start {
+                        let first = coords.cell_bounds(start.0.min(*row), start.1.min(*col));
+                        let last = coords.cell_bounds(start.0.max(*row), start.1.max(*col));
+                        let (width, height) = (last.z - first.x, last.w - first.y);
+                        draw_rectangle(first.x, first.y, width, height, ORANGE.with_alpha(0.3));
+                        draw_rectangle_lines(first.x, first.y, width, height, 4.0, ORANGE);
+                        if is_mouse_button_released(MouseButton::Left) {
+                            if game.clear_area(current_floor, start, (*row, *col)) == 0 {
+                                feedback = Some(("nothing to remove".to_string(), get_time()));
+                            }
+                            area_start = None;
+                        }
+                    }
+                }
+                Tool::Paint | Tool::Corridor => {
+                    let paint_room = |game: &GameState, (row, col)| {
+                        let level = game.get_map_level(current_floor);
+                        return Some(palette.room_for(&catalogue, level, row, col));
+                    };
+                    // a whole stroke is undone together
+                    if is_mouse_button_pressed(MouseButton::Left) {
+                        game.history.begin_step();
+                        stroke_last = Some((*row, *col));
+                        let new_room = paint_room(&game, (*row, *col));
+                        if palette.tool == Tool::Paint && *room != new_room {
+                            edit_result = game.build_room(current_floor, *row, *col, new_room);
+                        }
+                    }
+                    if let Some(last) = stroke_last
+                        && last != (*row, *col)
+                        && is_mouse_button_down(MouseButton::Left)
+                    {
+                        // the mouse can cross several cells in a frame, none of them are skipped
+                        let mut previous = last;
+                        for cell in grid::walk(last, (*row, *col)) {
+                            let result = if palette.tool == Tool::Paint {
+                                let new_room = paint_room(&game, cell);
+                                if game.dungeon.floors[current_floor].rooms[cell] == new_room {
+                                    Ok(())
+                                } else {
+                                    game.build_room(current_floor, cell.0, cell.1, new_room)
+                                }
+                            } else {
+                                game.extend_corridor(current_floor, previous, cell, &corridor_rooms)
+                            };
+                            // one failure, like running out of gold, shouldn't stop the rest of the stroke
+                            edit_result = edit_result.and(result);
+                            previous = cell;
+                        }
+                        stroke_last = Some((*row, *col));
+                    }
+                }
             }
-            if is_key_released(KeyCode::Q) {
-                game.update_room(*row, *col, room.map(|r| r.rotate_left()));
+            // right click or delete removes the room under the cursor
+            if room.is_some()
+                && (camera.right_clicked()
+                    || is_key_released(KeyCode::Delete)
+                    || is_key_released(KeyCode::Backspace))
+            {
+                edit_result = game.build_room(current_floor, *row, *col, None);
+            }
+            if !shift_down && is_key_released(KeyCode::E) {
+                edit_result = game.rotate_room(current_floor, *row, *col, true);
+            }
+            if !shift_down && is_key_released(KeyCode::Q) {
+                edit_result = game.rotate_room(current_floor, *row, *col, false);
+            }
+            // N opens another entrance in the room, shift+N closes it again
+            if is_key_released(KeyCode::N) {
+                edit_result = if shift_down {
+                    game.remove_entrance(current_floor, *row, *col)
+                } else {
+                    game.add_entrance(current_floor, *row, *col)
+                };
+            }
+            if let Err(e) = edit_result {
+                feedback = Some((e.to_string(), get_time()));
             }
         });
+        // letting go anywhere other than the map drops the rectangle and ends the stroke
+        if is_mouse_button_released(MouseButton::Left) {
+            area_start = None;
+            stroke_last = None;
+            game.history.end_step();
+        }
+
+        if let Some((message, shown_at)) = &feedback {
+            if get_time() - shown_at < FEEDBACK_SECONDS {
+                draw_text(message, 20.0, screen_height() - 20.0, 30.0, RED);
+            } else {
+                feedback = None;
+            }
+        }
+
+        if is_quit_requested() || get_time() - last_autosave >= AUTOSAVE_SECONDS {
+            last_autosave = get_time();
+            if let Err(e) = save::save_to_file(&game, save_path) {
+                feedback = Some((e.to_string(), get_time()));
+            }
+            if is_quit_requested() {
+                break;
+            }
+        }
+
+        if let Some(summary) = &away_summary {
+            draw_away_summary(summary);
+            if is_mouse_button_released(MouseButton::Left) || get_last_key_pressed().is_some() {
+                away_summary = None;
+            }
+        }
 
         next_frame().await
     }
@@ -0,0 +1,340 @@
+use crate::map::{MapLevel, room::SimpleRoomDrawInfo};
+
+// every cell is drawn as a CELL_SIZE x CELL_SIZE block of characters
+const CELL_SIZE: usize = 3;
+const CORNER: char = '+';
+const HORIZONTAL_WALL: char = '-';
+const VERTICAL_WALL: char = '|';
+const OPENING: char = ' ';
+const EMPTY: char = '.';
+
+impl MapLevel {
+    /// Draws the level as text, each room as a box with gaps in its walls for exits and its symbol in the middle:
+    ///
+    /// ```text
+    /// +-++-+...
+    /// |E   |...
+    /// +-++-+...
+    /// ```
+    pub fn to_ascii(&self) -> String {
+        let mut lines = Vec::new();
+        for room_row in self.rooms.rows() {
+            let mut block = vec![String::new(); CELL_SIZE];
+            for room in room_row.iter() {
+                let cell = match room {
+                    Some(room) => room_to_ascii(room),
+                    None => [[EMPTY; CELL_SIZE]; CELL_SIZE],
+                };
+                for (line, cell_line) in block.iter_mut().zip(cell.iter()) {
+                    line.extend(cell_line.iter());
+                }
+            }
+            lines.extend(block);
+        }
+        return lines.join("\n") + "\n";
+    }
+}
+
+fn room_to_ascii(room: &SimpleRoomDrawInfo) -> [[char; CELL_SIZE]; CELL_SIZE] {
+    let wall = |exit: bool, wall: char| if exit { OPENING } else { wall };
+    return [
+        [CORNER, wall(room.top_exit, HORIZONTAL_WALL), CORNER],
+        [
+            wall(room.left_exit, VERTICAL_WALL),
+            room.symbol.unwrap_or(OPENING),
+            wall(room.right_exit, VERTICAL_WALL),
+        ],
+        [CORNER, wall(room.bottom_exit, HORIZONTAL_WALL), CORNER],
+    ];
+}
+
+// only tests read levels back in, the game itself only exports them
+#[cfg(test)]
+mod parse {
+    use std::fmt;
+
+    use super::{CELL_SIZE, CORNER, EMPTY, HORIZONTAL_WALL, OPENING, VERTICAL_WALL};
+    use crate::{
+        map::{
+            Direction, MapLevel,
+            catalogue::{RoomCatalogue, RoomDefinition},
+            room::{ROOM_BODY, SimpleRoomDrawInfo},
+        },
+        resources::Resources,
+    };
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct AsciiError {
+        // 1 based, like a text editor
+        pub line: usize,
+        pub column: usize,
+        pub reason: String,
+    }
+
+    impl fmt::Display for AsciiError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}:{}: {}", self.line, self.column, self.reason)
+        }
+    }
+
+    impl MapLevel {
+        /// Reads a level drawn by [MapLevel::to_ascii]. Blank lines around the drawing are ignored and lines may lose
+        /// their trailing spaces. A room's costs, colour and production come from the room in `catalogue` found by
+        /// [catalogue_room]
+        pub fn from_ascii(text: &str, catalogue: &RoomCatalogue) -> Result<MapLevel, AsciiError> {
+            let all_lines: Vec<&str> = text.lines().collect();
+            let first = all_lines.iter().position(|x| !x.trim().is_empty());
+            let last = all_lines.iter().rposition(|x| !x.trim().is_empty());
+            let (first, last) = match (first, last) {
+                (Some(first), Some(last)) => (first, last),
+                _ => {
+                    return Err(AsciiError {
+                        line: 1,
+                        column: 1,
+                        reason: "there is no map".to_string(),
+                    });
+                }
+            };
+            let lines: Vec<Vec<char>> = all_lines[first..=last]
+                .iter()
+                .map(|x| x.chars().collect())
+                .collect();
+            if !lines.len().is_multiple_of(CELL_SIZE) {
+                return Err(AsciiError {
+                    line: last + 1,
+                    column: 1,
+                    reason: format!(
+                        "{} lines can't be split into rows of {CELL_SIZE}",
+                        lines.len()
+                    ),
+                });
+            }
+            let width = lines
+                .iter()
+                .map(|x| x.len())
+                .max()
+                .unwrap_or(0)
+                .div_ceil(CELL_SIZE);
+            let height = lines.len() / CELL_SIZE;
+
+            let mut level = MapLevel::new(width, height);
+            for row in 0..height {
+                for col in 0..width {
+                    // trailing spaces may have been trimmed, so anything past the end of a line is an opening
+                    let cell: [[char; CELL_SIZE]; CELL_SIZE] = std::array::from_fn(|y| {
+                        std::array::from_fn(|x| {
+                            *lines[row * CELL_SIZE + y]
+                                .get(col * CELL_SIZE + x)
+                                .unwrap_or(&OPENING)
+                        })
+                    });
+                    level.rooms[(row, col)] =
+                        room_from_ascii(&cell, catalogue).map_err(|(y, x, reason)| AsciiError {
+                            line: first + row * CELL_SIZE + y + 1,
+                            column: col * CELL_SIZE + x + 1,
+                            reason,
+                        })?;
+                }
+            }
+            return Ok(level);
+        }
+    }
+
+    /// The room drawn in a cell, errors give the row and column within the cell
+    fn room_from_ascii(
+        cell: &[[char; CELL_SIZE]; CELL_SIZE],
+        catalogue: &RoomCatalogue,
+    ) -> Result<Option<SimpleRoomDrawInfo>, (usize, usize, String)> {
+        if cell.iter().flatten().all(|x| *x == EMPTY) {
+            return Ok(None);
+        }
+        for (y, x) in [(0, 0), (0, 2), (2, 0), (2, 2)] {
+            if cell[y][x] != CORNER {
+                return Err((
+                    y,
+                    x,
+                    format!("expected a '{CORNER}' corner or an empty cell"),
+                ));
+            }
+        }
+        let exit = |y: usize, x: usize, wall: char| match cell[y][x] {
+            OPENING => Ok(true),
+            c if c == wall => Ok(false),
+            c => Err((y, x, format!("'{c}' should be '{wall}' or an opening"))),
+        };
+        let symbol = match cell[1][1] {
+            OPENING => None,
+            c => Some(c),
+        };
+        // stairs are drawn with the symbol the catalogue gives them
+        let stairs = catalogue
+            .rooms
+            .iter()
+            .find(|x| x.room.stairs.is_some() && symbol.is_some() && x.room.symbol == symbol)
+            .and_then(|x| x.room.stairs);
+        let mut room = SimpleRoomDrawInfo {
+            top_exit: exit(0, 1, HORIZONTAL_WALL)?,
+            left_exit: exit(1, 0, VERTICAL_WALL)?,
+            right_exit: exit(1, 2, VERTICAL_WALL)?,
+            bottom_exit: exit(2, 1, HORIZONTAL_WALL)?,
+            symbol,
+            stairs,
+            cost: Resources::ZERO,
+            traversal_cost: 1,
+            colour: ROOM_BODY,
+            production: Resources::ZERO,
+            indestructible: false,
+        };
+        if let Some(definition) = catalogue_room(catalogue, &room) {
+            room.cost = definition.room.cost;
+            room.traversal_cost = definition.room.traversal_cost;
+            room.colour = definition.room.colour;
+            room.production = definition.room.production;
+            room.indestructible = definition.room.indestructible;
+        }
+        return Ok(Some(room));
+    }
+
+    /// The room in `catalogue` that can be turned to have the same exits and stairs as `room`. One with the same
+    /// symbol is preferred, then one without a symbol
+    fn catalogue_room<'a>(
+        catalogue: &'a RoomCatalogue,
+        room: &SimpleRoomDrawInfo,
+    ) -> Option<&'a RoomDefinition> {
+        let turns_into = |x: SimpleRoomDrawInfo| {
+            x.stairs == room.stairs
+                && std::iter::successors(Some(x), |r| Some(r.rotate_right()))
+                    .take(4)
+                    .any(|r| {
+                        Direction::ALL
+                            .into_iter()
+                            .all(|side| r.has_exit(side) == room.has_exit(side))
+                    })
+        };
+        let matching = || catalogue.rooms.iter().filter(|x| turns_into(x.room));
+        return matching()
+            .find(|x| x.room.symbol == room.symbol)
+            .or_else(|| matching().find(|x| x.room.symbol.is_none()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::{
+        Dungeon, MapLevel, TraversalMode,
+        catalogue::RoomCatalogue,
+        grid::Grid,
+        room::{SimpleRoomDrawInfo, room_type},
+    };
+
+    #[test]
+    fn test_ascii_round_trip() {
+        let map = MapLevel::from_ascii(
+            "
++-++-++-+
+|       |
++ ++-++ +
++ ++ ++ +
+|  |    |
++-++-++-+
+",
+            &RoomCatalogue::built_in(),
+        )
+        .unwrap();
+
+        let enter_up_right = room_type::L;
+        let enter_down_right = enter_up_right.rotate_right();
+        let enter_down_left = enter_down_right.rotate_right();
+        let enter_left_up = enter_down_left.rotate_right();
+        let expected = MapLevel {
+            rooms: Grid::from([
+                [
+                    Some(enter_down_right),
+                    Some(room_type::HALL.rotate_right()),
+                    Some(enter_down_left),
+                ],
+                [
+                    Some(enter_up_right),
+                    Some(enter_up_right),
+                    Some(enter_left_up),
+                ],
+            ]),
+        };
+        assert_eq!(map, expected);
+        assert_eq!(
+            MapLevel::from_ascii(&map.to_ascii(), &RoomCatalogue::built_in()),
+            Ok(map)
+        );
+
+        let mut with_gaps = MapLevel::new(3, 2);
+        with_gaps.rooms[(0, 1)] = Some(room_type::STAIRS_DOWN.rotate_left());
+        with_gaps.rooms[(1, 0)] = Some(room_type::CROSSING);
+        with_gaps.rooms[(1, 2)] = Some(SimpleRoomDrawInfo {
+            symbol: Some('E'),
+            ..room_type::T
+        });
+        assert_eq!(
+            with_gaps.to_ascii(),
+            "\
+...+-+...
+... >|...
+...+-+...
++ +...+ +
+   ... E 
++ +...+-+
+"
+        );
+        assert_eq!(
+            MapLevel::from_ascii(&with_gaps.to_ascii(), &RoomCatalogue::built_in()),
+            Ok(with_gaps)
+        );
+    }
+
+    #[test]
+    fn test_ascii_traversal_fixture() {
+        let map = MapLevel::from_ascii(
+            "
++-++-++-+
+|   E   |
++-++ ++-+
+...+ +...
+...|>|...
+...+-+...
+",
+            &RoomCatalogue::built_in(),
+        )
+        .unwrap();
+
+        let mut depths = Vec::new();
+        let dungeon = Dungeon {
+            floors: vec![map.clone()],
+        };
+        let errors = dungeon.traverse_entrances(&[(0, 1)], TraversalMode::Breadth, |ti| {
+            depths.push((ti.row, ti.col, ti.depth))
+        });
+        assert!(errors.is_empty());
+        assert_eq!(depths, vec![(0, 1, 0), (0, 0, 1), (0, 2, 1), (1, 1, 1)]);
+        assert_eq!(
+            map.rooms[(1, 1)].unwrap().stairs,
+            room_type::STAIRS_DOWN.stairs
+        );
+    }
+
+    #[test]
+    fn test_ascii_errors() {
+        let error =
+            MapLevel::from_ascii("+-+\n|x|\n+-?\n", &RoomCatalogue::built_in()).unwrap_err();
+        assert_eq!((error.line, error.column), (3, 3));
+
+        let error =
+            MapLevel::from_ascii("\n+-+\n|x|\n+-+\n+-+\n", &RoomCatalogue::built_in()).unwrap_err();
+        assert_eq!(error.line, 5);
+
+        // a cell that is neither a room nor empty is reported at its first corner
+        let error = MapLevel::from_ascii("+-+...\n|x|...\n+-+.x.\n", &RoomCatalogue::built_in())
+            .unwrap_err();
+        assert_eq!((error.line, error.column), (1, 4));
+
+        assert!(MapLevel::from_ascii("  \n\n", &RoomCatalogue::built_in()).is_err());
+    }
+}
@@ -0,0 +1,405 @@
+use std::{fmt, fs, io, path::Path, str::FromStr};
+
+use macroquad::color::Color;
+
+use crate::{
+    map::room::{ROOM_BODY, SimpleRoomDrawInfo, Stairs},
+    resources::Resources,
+};
+
+pub const CATALOGUE_PATH: &str = "assets/rooms.txt";
+// the same file compiled in, so the game still has rooms to offer when it is missing
+const BUILT_IN: &str = include_str!("../../assets/rooms.txt");
+
+/// A room players can build, as described by the catalogue
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoomDefinition {
+    pub name: String,
+    pub description: String,
+    pub room: SimpleRoomDrawInfo,
+}
+
+/// Every room players can build, read from a text file so new rooms don't need code changes. The format is
+/// described at the top of [CATALOGUE_PATH]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoomCatalogue {
+    // in the order the game offers them
+    pub rooms: Vec<RoomDefinition>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CatalogueError {
+    // 1 based, 0 for problems with the catalogue as a whole
+    pub line: usize,
+    // the room being read when the problem was found
+    pub room: Option<String>,
+    pub reason: String,
+}
+
+impl fmt::Display for CatalogueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, &self.room) {
+            (0, _) => write!(f, "room catalogue: {}", self.reason),
+            (line, Some(room)) => {
+                write!(f, "room catalogue line {line} ({room}): {}", self.reason)
+            }
+            (line, None) => write!(f, "room catalogue line {line}: {}", self.reason),
+        }
+    }
+}
+
+impl RoomCatalogue {
+    /// The rooms the game ships with
+    pub fn built_in() -> Self {
+        return RoomCatalogue::parse(BUILT_IN)
+            .expect("The built in room catalogue should be valid");
+    }
+
+    /// Reads the catalogue at `path`, falling back to [RoomCatalogue::built_in] when there is no file there
+    pub fn load(path: &Path) -> Result<Self, CatalogueError> {
+        return match fs::read_to_string(path) {
+            Ok(text) => RoomCatalogue::parse(&text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(RoomCatalogue::built_in()),
+            Err(e) => Err(CatalogueError {
+                line: 0,
+                room: None,
+                reason: format!("couldn't read {}: {e}", path.display()),
+            }),
+        };
+    }
+
+    pub fn parse(text: &str) -> Result<Self, CatalogueError> {
+        let mut rooms: Vec<RoomDefinition> = Vec::new();
+        let mut current: Option<RoomReader> = None;
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(char::is_whitespace)
+                .map_or((line, ""), |(key, value)| (key, value.trim()));
+            if key != "room" {
+                let Some(reader) = current.as_mut() else {
+                    return Err(CatalogueError {
+                        line: line_number,
+                        room: None,
+                        reason: format!("'{key}' comes before the first room"),
+                    });
+                };
+                reader.read(key, value).map_err(|reason| CatalogueError {
+                    line: line_number,
+                    room: Some(reader.definition.name.clone()),
+                    reason,
+                })?;
+                continue;
+            }
+
+            if let Some(reader) = current.take() {
+                rooms.push(reader.finish()?);
+            }
+            if value.is_empty() {
+                return Err(CatalogueError {
+                    line: line_number,
+                    room: None,
+                    reason: "the room has no name".to_string(),
+                });
+            }
+            if rooms.iter().any(|x| x.name == value) {
+                return Err(CatalogueError {
+                    line: line_number,
+                    room: Some(value.to_string()),
+                    reason: "there is already a room with this name".to_string(),
+                });
+            }
+            current = Some(RoomReader::new(line_number, value));
+        }
+        if let Some(reader) = current.take() {
+            rooms.push(reader.finish()?);
+        }
+        if rooms.is_empty() {
+            return Err(CatalogueError {
+                line: 0,
+                room: None,
+                reason: "there are no rooms".to_string(),
+            });
+        }
+        return Ok(RoomCatalogue { rooms });
+    }
+}
+
+/// A room part way through being read, each property is checked as it is read
+struct RoomReader {
+    // where the room starts, for problems found once all of it has been read
+    line: usize,
+    definition: RoomDefinition,
+    read_keys: Vec<String>,
+}
+
+impl RoomReader {
+    fn new(line: usize, name: &str) -> Self {
+        return RoomReader {
+            line,
+            definition: RoomDefinition {
+                name: name.to_string(),
+                description: String::new(),
+                room: SimpleRoomDrawInfo {
+                    left_exit: false,
+                    right_exit: false,
+                    top_exit: false,
+                    bottom_exit: false,
+                    symbol: None,
+                    stairs: None,
+                    cost: Resources::ZERO,
+                    traversal_cost: 1,
+                    colour: ROOM_BODY,
+                    production: Resources::ZERO,
+                    indestructible: false,
+                },
+            },
+            read_keys: Vec::new(),
+        };
+    }
+
+    fn read(&mut self, key: &str, value: &str) -> Result<(), String> {
+        if self.read_keys.iter().any(|x| x == key) {
+            return Err(format!("'{key}' is given more than once"));
+        }
+        let room = &mut self.definition.room;
+        match key {
+            "exits" => {
+                for side in value.split_whitespace() {
+                    match side {
+                        "left" => room.left_exit = true,
+                        "right" => room.right_exit = true,
+                        "top" => room.top_exit = true,
+                        "bottom" => room.bottom_exit = true,
+                        "none" => {}
+                        side => {
+                            return Err(format!(
+                                "'{side}' is not a side, exits can be left, right, top, bottom or none"
+                            ));
+                        }
+                    }
+                }
+            }
+            "cost" => room.cost = parse_resources(value)?,
+            "symbol" => {
+                let mut chars = value.chars();
+                room.symbol = match (chars.next(), chars.next()) {
+                    (Some(x), None) => Some(x),
+                    _ => return Err(format!("'{value}' is not a single character")),
+                };
+            }
+            "stairs" => {
+                room.stairs = match value {
+                    "up" => Some(Stairs::Up),
+                    "down" => Some(Stairs::Down),
+                    _ => return Err(format!("'{value}' is not up or down")),
+                };
+            }
+            "traversal_cost" => room.traversal_cost = parse_number(value, "traversal cost")?,
+            "colour" => {
+                let parts = parse_numbers::<f32>(value, 3, "colour component")?;
+                if parts.iter().any(|x| !(0.0..=1.0).contains(x)) {
+                    return Err(format!("'{value}' has a colour component outside 0 to 1"));
+                }
+                room.colour = Color::new(parts[0], parts[1], parts[2], 1.0);
+            }
+            "production" => room.production = parse_resources(value)?,
+            "indestructible" => {
+                room.indestructible = match value {
+                    "yes" => true,
+                    "no" => false,
+                    _ => return Err(format!("'{value}' is not yes or no")),
+                };
+            }
+            "description" => self.definition.description = value.to_string(),
+            key => return Err(format!("unknown property '{key}'")),
+        }
+        self.read_keys.push(key.to_string());
+        return Ok(());
+    }
+
+    fn finish(self) -> Result<RoomDefinition, CatalogueError> {
+        for required in ["exits", "cost"] {
+            if !self.read_keys.iter().any(|x| x == required) {
+                return Err(CatalogueError {
+                    line: self.line,
+                    room: Some(self.definition.name),
+                    reason: format!("missing '{required}'"),
+                });
+            }
+        }
+        return Ok(self.definition);
+    }
+}
+
+fn parse_number<T: FromStr>(value: &str, what: &str) -> Result<T, String> {
+    return value
+        .parse()
+        .map_err(|_| format!("'{value}' is not a valid {what}"));
+}
+
+fn parse_numbers<T: FromStr>(value: &str, count: usize, what: &str) -> Result<Vec<T>, String> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    if parts.len() != count {
+        return Err(format!(
+            "expected {count} numbers but found {}",
+            parts.len()
+        ));
+    }
+    return parts.iter().map(|x| parse_number(x, what)).collect();
+}
+
+/// Gold, mana and souls in that order
+fn parse_resources(value: &str) -> Result<Resources, String> {
+    let parts = parse_numbers::<u64>(value, 3, "amount")?;
+    return Ok(Resources {
+        gold: parts[0],
+        mana: parts[1],
+        souls: parts[2],
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use macroquad::prelude::*;
+
+    use crate::map::{
+        MapLevel,
+        catalogue::{CATALOGUE_PATH, RoomCatalogue},
+        room::{ROOM_BODY, SimpleRoomDrawInfo, room_type},
+    };
+    use crate::resources::Resources;
+
+    #[test]
+    fn test_built_in_catalogue() {
+        let catalogue = RoomCatalogue::built_in();
+        // the rooms tests build with are ones the game ships
+        let rooms: Vec<SimpleRoomDrawInfo> = catalogue.rooms.iter().map(|x| x.room).collect();
+        assert!(room_type::ALL_TYPES.iter().all(|x| rooms.contains(x)));
+        assert!(
+            catalogue
+                .rooms
+                .iter()
+                .any(|x| x.name == "hall" && x.room == room_type::HALL)
+        );
+        assert!(catalogue.rooms.iter().all(|x| !x.description.is_empty()));
+    }
+
+    #[test]
+    fn test_catalogue() {
+        let catalogue = RoomCatalogue::parse(
+            "
+# comments and blank lines are skipped
+room treasury
+exits left bottom
+cost 50 10 0
+symbol $
+colour 1 0.8 0
+production 2 0 1
+indestructible yes
+description Pays out while adventurers can reach it
+
+room cell
+exits none
+cost 5 0 0
+",
+        )
+        .unwrap();
+        assert_eq!(catalogue.rooms.len(), 2);
+        let treasury = &catalogue.rooms[0];
+        assert_eq!(treasury.name, "treasury");
+        assert_eq!(
+            treasury.description,
+            "Pays out while adventurers can reach it"
+        );
+        assert!(treasury.room.left_exit && treasury.room.bottom_exit);
+        assert!(!treasury.room.top_exit && !treasury.room.right_exit);
+        assert_eq!(treasury.room.symbol, Some('$'));
+        assert_eq!(treasury.room.colour, Color::new(1.0, 0.8, 0.0, 1.0));
+        assert_eq!(
+            treasury.room.production,
+            Resources {
+                gold: 2,
+                mana: 0,
+                souls: 1,
+            }
+        );
+        assert!(treasury.room.indestructible);
+        // anything left out gets a default
+        assert!(!catalogue.rooms[1].room.indestructible);
+        assert_eq!(catalogue.rooms[1].room.traversal_cost, 1);
+        assert_eq!(catalogue.rooms[1].room.colour, ROOM_BODY);
+    }
+
+    #[test]
+    fn test_ascii_uses_catalogue() {
+        // the catalogue players edit only has to be valid, it can offer whatever rooms it likes
+        assert!(RoomCatalogue::load(Path::new(CATALOGUE_PATH)).is_ok());
+
+        let catalogue = RoomCatalogue::parse(
+            "
+room corner
+exits left bottom
+cost 20 0 0
+
+room treasury
+exits left bottom
+cost 50 10 0
+symbol $
+production 2 0 1
+",
+        )
+        .unwrap();
+        let level = MapLevel::from_ascii(
+            "
++-++-++-++-+
+ $|  || ||E|
++ ++ ++-++-+
+",
+            &catalogue,
+        )
+        .unwrap();
+        // a symbol picks the room with that symbol, anything else the room without one
+        let treasury = level.rooms[(0, 0)].unwrap();
+        assert_eq!(treasury.cost, catalogue.rooms[1].room.cost);
+        assert_eq!(treasury.production, catalogue.rooms[1].room.production);
+        assert_eq!(level.rooms[(0, 1)].unwrap().cost, Resources::gold(20));
+        // rooms the catalogue doesn't have cost nothing
+        assert_eq!(level.rooms[(0, 2)].unwrap().cost, Resources::ZERO);
+        assert_eq!(level.rooms[(0, 3)].unwrap().symbol, Some('E'));
+    }
+
+    #[test]
+    fn test_catalogue_errors() {
+        let error = |text: &str| RoomCatalogue::parse(text).unwrap_err();
+
+        let bad_exit = error("room a\nexits top\ncost 1 0 0\n\nroom b\nexits up\n");
+        assert_eq!((bad_exit.line, bad_exit.room.as_deref()), (6, Some("b")));
+        assert!(bad_exit.reason.contains("'up'"));
+        assert_eq!(
+            bad_exit.to_string(),
+            format!("room catalogue line 6 (b): {}", bad_exit.reason)
+        );
+
+        // missing properties are reported where the room starts
+        let missing = error("room a\nexits top\ncost 1 0 0\nroom b\nexits top\n");
+        assert_eq!((missing.line, missing.room.as_deref()), (4, Some("b")));
+        assert!(missing.reason.contains("cost"));
+
+        assert_eq!(error("cost 1 0 0\n").line, 1);
+        assert_eq!(error("room a\nexits top\ncost 1 0 0\nroom a\n").line, 4);
+        assert_eq!(error("room a\ncost 1 0\n").line, 2);
+        assert_eq!(error("room a\ncolour 1 2 0\n").line, 2);
+        assert_eq!(error("room a\nsymbol ab\n").line, 2);
+        assert_eq!(error("room a\nexits top\nexits left\n").line, 3);
+        assert_eq!(error("room a\nsize 3\n").line, 2);
+        assert_eq!(error("room a\nindestructible maybe\n").line, 2);
+        assert_eq!(error("# nothing here\n").line, 0);
+    }
+}
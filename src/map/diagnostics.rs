@@ -0,0 +1,174 @@
+use std::{collections::HashMap, fmt};
+
+use crate::map::{Cell, Direction, Dungeon, TraversalMode};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitProblem {
+    // the exit leads off the side of the map
+    MapEdge,
+    // the exit opens onto a cell with no room in it
+    EmptyCell,
+    // the room next door has a wall on this side, so the two don't connect
+    NoMatchingExit,
+}
+
+impl fmt::Display for ExitProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitProblem::MapEdge => write!(f, "opens onto the edge of the map"),
+            ExitProblem::EmptyCell => write!(f, "opens onto an empty cell"),
+            ExitProblem::NoMatchingExit => write!(f, "faces a wall next door"),
+        }
+    }
+}
+
+/// Everything that stops rooms from being connected the way they look like they should be
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    // rooms no entrance can reach, they never earn anything
+    pub unreachable: Vec<Cell>,
+    pub exits: Vec<(Cell, Direction, ExitProblem)>,
+    // one entry per independent loop, the rooms around it in order
+    pub cycles: Vec<Vec<Cell>>,
+}
+
+impl Dungeon {
+    /// Checks every room of every floor against the entrances, which are on the top floor
+    pub fn diagnose(&self, entrance_rowcols: &[(usize, usize)]) -> Diagnostics {
+        let mut diagnostics = Diagnostics::default();
+
+        // the spanning forest the traversal walks, every connection it doesn't use closes a loop
+        let mut parents: HashMap<Cell, Option<Cell>> = HashMap::new();
+        let mut order: Vec<Cell> = Vec::new();
+        self.traverse_entrances(entrance_rowcols, TraversalMode::Breadth, |ti| {
+            order.push((ti.floor, ti.row as usize, ti.col as usize))
+        });
+        let visit_index: HashMap<Cell, usize> =
+            order.iter().enumerate().map(|(i, x)| (*x, i)).collect();
+        for (i, cell) in order.iter().enumerate() {
+            // the first neighbour visited is one step closer to an entrance, entrances usually have none
+            let parent = self
+                .neighbours(cell.0, cell.1, cell.2)
+                .into_iter()
+                .filter(|x| visit_index[x] < i)
+                .min_by_key(|x| visit_index[x]);
+            parents.insert(*cell, parent);
+        }
+
+        for (floor, level) in self.floors.iter().enumerate() {
+            for (row, col, room) in level.rooms.iter_some() {
+                let cell = (floor, row, col);
+                if !parents.contains_key(&cell) {
+                    diagnostics.unreachable.push(cell);
+                }
+                for side in Direction::ALL.into_iter().filter(|x| room.has_exit(*x)) {
+                    let (row_add, col_add) = side.offset();
+                    let (next_row, next_col) = (row as isize + row_add, col as isize + col_add);
+                    let problem = if !level.rooms.in_bounds(next_row, next_col) {
+                        ExitProblem::MapEdge
+                    } else {
+                        match level.rooms[(next_row as usize, next_col as usize)] {
+                            None => ExitProblem::EmptyCell,
+                            Some(next) if !next.has_exit(side.opposite()) => {
+                                ExitProblem::NoMatchingExit
+                            }
+                            Some(_) => continue,
+                        }
+                    };
+                    diagnostics.exits.push((cell, side, problem));
+                }
+            }
+        }
+
+        diagnostics.cycles = self.cycles(&parents);
+        return diagnostics;
+    }
+
+    /// Every connection between two reached rooms that isn't part of the spanning forest, as the loop it closes
+    fn cycles(&self, parents: &HashMap<Cell, Option<Cell>>) -> Vec<Vec<Cell>> {
+        let path_to_root = |mut cell: Cell| {
+            let mut path = vec![cell];
+            while let Some(Some(parent)) = parents.get(&cell) {
+                path.push(*parent);
+                cell = *parent;
+            }
+            return path;
+        };
+        let mut cells: Vec<&Cell> = parents.keys().collect();
+        cells.sort();
+        let mut cycles = Vec::new();
+        for cell in cells {
+            for next in self.neighbours(cell.0, cell.1, cell.2) {
+                // each connection is seen from both ends, only the one from the lower cell counts
+                if next <= *cell || parents[cell] == Some(next) || parents[&next] == Some(*cell) {
+                    continue;
+                }
+                let mut from_cell = path_to_root(*cell);
+                let mut from_next = path_to_root(next);
+                // drop the shared part above where the two paths meet, keeping the meeting room once
+                while from_cell.len() > 1
+                    && from_next.len() > 1
+                    && from_cell[from_cell.len() - 2] == from_next[from_next.len() - 2]
+                {
+                    from_cell.pop();
+                    from_next.pop();
+                }
+                if from_cell.last() != from_next.last() {
+                    // reached from different entrances, the loop runs through the outside of the dungeon
+                    continue;
+                }
+                from_next.pop();
+                from_next.reverse();
+                from_cell.extend(from_next);
+                cycles.push(from_cell);
+            }
+        }
+        return cycles;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::{
+        Direction, Dungeon, MapLevel, catalogue::RoomCatalogue, diagnostics::ExitProblem,
+    };
+
+    #[test]
+    fn test_diagnostics() {
+        // a loop of four rooms, one room off to the side facing a wall and two exits leading nowhere
+        let level = MapLevel::from_ascii(
+            "
++-++-++-+
+|    |  |
++ ++ ++-+
++ ++ +...
+|     ...
++ ++-+...
+",
+            &RoomCatalogue::built_in(),
+        )
+        .unwrap();
+        let dungeon = Dungeon {
+            floors: vec![level],
+        };
+        let diagnostics = dungeon.diagnose(&[(0, 0)]);
+        assert_eq!(diagnostics.unreachable, vec![(0, 0, 2)]);
+        assert_eq!(
+            diagnostics.exits,
+            vec![
+                ((0, 0, 2), Direction::Left, ExitProblem::NoMatchingExit),
+                ((0, 1, 0), Direction::Bottom, ExitProblem::MapEdge),
+                ((0, 1, 1), Direction::Right, ExitProblem::EmptyCell),
+            ]
+        );
+        assert_eq!(
+            diagnostics.cycles,
+            vec![vec![(0, 1, 0), (0, 0, 0), (0, 0, 1), (0, 1, 1)]]
+        );
+
+        // without an entrance nothing is reachable and there are no loops to speak of
+        let diagnostics = dungeon.diagnose(&[]);
+        assert_eq!(diagnostics.unreachable.len(), 5);
+        assert!(diagnostics.cycles.is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+use std::ops::{Index, IndexMut};
+
+use crate::map::Direction;
+
+/// A heap backed, row-major 2d grid of optional cells whose size is chosen at runtime
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<Option<T>>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize) -> Self {
+        return Grid {
+            width,
+            height,
+            cells: (0..width * height).map(|_| None).collect(),
+        };
+    }
+
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+    pub fn in_bounds(&self, row: isize, col: isize) -> bool {
+        return row >= 0 && col >= 0 && (row as usize) < self.height && (col as usize) < self.width;
+    }
+
+    /// Empties every cell without changing the size of the grid
+    pub fn clear(&mut self) {
+        self.cells.iter_mut().for_each(|cell| *cell = None);
+    }
+
+    /// Adds `count` empty rows or columns on the given side, existing cells keep their contents but
+    /// move along with the rest of the grid when growing towards the top or left
+    pub fn expand(&mut self, side: Direction, count: usize) {
+        let (row_offset, col_offset) = match side {
+            Direction::Top => (count, 0),
+            Direction::Left => (0, count),
+            Direction::Bottom | Direction::Right => (0, 0),
+        };
+        let (new_width, new_height) = match side {
+            Direction::Left | Direction::Right => (self.width + count, self.height),
+            Direction::Top | Direction::Bottom => (self.width, self.height + count),
+        };
+        let mut new_cells: Vec<Option<T>> = (0..new_width * new_height).map(|_| None).collect();
+        for (i, cell) in self.cells.drain(..).enumerate() {
+            let (row, col) = (i / self.width, i % self.width);
+            new_cells[(row + row_offset) * new_width + col + col_offset] = cell;
+        }
+        self.width = new_width;
+        self.height = new_height;
+        self.cells = new_cells;
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[Option<T>]> {
+        // chunks panics on a size of 0, a 0 width grid has no cells anyway
+        return self.cells.chunks(self.width.max(1));
+    }
+
+    /// Every occupied cell as (row, col, value)
+    pub fn iter_some(&self) -> impl Iterator<Item = (usize, usize, &T)> {
+        let width = self.width;
+        return self
+            .cells
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, cell)| cell.as_ref().map(|x| (i / width, i % width, x)));
+    }
+}
+
+/// The cells passed through going from `from` to `to` one side at a time, not including `from`. Each step is
+/// along whichever axis has further to go, so the walk stays close to a straight line
+pub fn walk(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let step = |from: usize, to: usize| if to > from { from + 1 } else { from - 1 };
+    let mut cells = Vec::new();
+    let mut current = from;
+    while current != to {
+        if to.0.abs_diff(current.0) > to.1.abs_diff(current.1) {
+            current.0 = step(current.0, to.0);
+        } else {
+            current.1 = step(current.1, to.1);
+        }
+        cells.push(current);
+    }
+    return cells;
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = Option<T>;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        assert!(
+            row < self.height && col < self.width,
+            "({row}, {col}) is outside of a {}x{} grid",
+            self.width,
+            self.height
+        );
+        return &self.cells[row * self.width + col];
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Self::Output {
+        assert!(
+            row < self.height && col < self.width,
+            "({row}, {col}) is outside of a {}x{} grid",
+            self.width,
+            self.height
+        );
+        return &mut self.cells[row * self.width + col];
+    }
+}
+
+impl<T, const W: usize, const H: usize> From<[[Option<T>; W]; H]> for Grid<T> {
+    fn from(rows: [[Option<T>; W]; H]) -> Self {
+        return Grid {
+            width: W,
+            height: H,
+            cells: rows.into_iter().flatten().collect(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::{Direction, grid};
+
+    #[test]
+    fn test_walk() {
+        assert_eq!(grid::walk((2, 2), (2, 2)), vec![]);
+        assert_eq!(grid::walk((2, 2), (2, 0)), vec![(2, 1), (2, 0)]);
+        // diagonal moves alternate between rows and columns, every step is to a neighbour
+        let walked = grid::walk((0, 0), (2, 3));
+        assert_eq!(walked, vec![(0, 1), (0, 2), (1, 2), (1, 3), (2, 3)]);
+        assert_eq!(Direction::between((0, 0), (0, 1)), Some(Direction::Right));
+        assert_eq!(Direction::between((1, 2), (0, 2)), Some(Direction::Top));
+        assert_eq!(Direction::between((0, 0), (1, 1)), None);
+        assert!(
+            walked
+                .windows(2)
+                .all(|x| Direction::between(x[0], x[1]).is_some())
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use macroquad::prelude::*;
+
+use crate::map::{TraversalInfo, grid::Grid};
+
+// shallowest to deepest
+const GRADIENT: [Color; 3] = [SKYBLUE, YELLOW, RED];
+const UNREACHABLE: Color = DARKGRAY;
+const TINT_ALPHA: f32 = 0.5;
+const LEGEND_STEPS: usize = 20;
+
+/// Tints rooms by how deep they are, see [MapLevel::draw](crate::map::MapLevel::draw)
+pub struct Heatmap<'a> {
+    pub traversal_info: &'a Grid<TraversalInfo>,
+    // the depth that gets the hottest colour, shared by every floor so their colours can be compared
+    pub max_depth: i32,
+}
+
+impl Heatmap<'_> {
+    pub fn depth_colour(&self, depth: i32) -> Color {
+        let position = if self.max_depth > 0 {
+            depth.clamp(0, self.max_depth) as f32 / self.max_depth as f32
+        } else {
+            0.0
+        };
+        let scaled = position * (GRADIENT.len() - 1) as f32;
+        let index = (scaled.floor() as usize).min(GRADIENT.len() - 2);
+        let (from, to, t) = (GRADIENT[index], GRADIENT[index + 1], scaled - index as f32);
+        let mix = |from: f32, to: f32| from * (1.0 - t) + to * t;
+        return Color::new(
+            mix(from.r, to.r),
+            mix(from.g, to.g),
+            mix(from.b, to.b),
+            TINT_ALPHA,
+        );
+    }
+
+    /// The tint for the room at (row, col), grey when no entrance reaches it
+    pub fn room_colour(&self, row: usize, col: usize) -> Color {
+        return match self.traversal_info[(row, col)] {
+            Some(ti) => self.depth_colour(ti.depth),
+            None => UNREACHABLE.with_alpha(0.7),
+        };
+    }
+
+    /// A bar running through the gradient from depth 0 to [Heatmap::max_depth], with a swatch for unreachable rooms
+    pub fn draw_legend(&self, top_left: Vec2, width: f32) {
+        let height = 16.0;
+        let step_width = width / LEGEND_STEPS as f32;
+        for i in 0..LEGEND_STEPS {
+            let depth = (self.max_depth as f32 * i as f32 / (LEGEND_STEPS - 1) as f32).round();
+            draw_rectangle(
+                top_left.x + i as f32 * step_width,
+                top_left.y,
+                step_width,
+                height,
+                self.depth_colour(depth as i32).with_alpha(1.0),
+            );
+        }
+        draw_text(
+            "depth 0",
+            top_left.x,
+            top_left.y + height + 16.0,
+            18.0,
+            BLACK,
+        );
+        let max_label = format!("{}", self.max_depth);
+        let label_width = measure_text(&max_label, None, 18, 1.0).width;
+        draw_text(
+            &max_label,
+            top_left.x + width - label_width,
+            top_left.y + height + 16.0,
+            18.0,
+            BLACK,
+        );
+        let swatch_x = top_left.x + width + 20.0;
+        draw_rectangle(swatch_x, top_left.y, height, height, UNREACHABLE);
+        draw_text(
+            "unreachable",
+            swatch_x + height + 6.0,
+            top_left.y + height - 2.0,
+            18.0,
+            BLACK,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::prelude::*;
+
+    use crate::map::{TraversalInfo, grid::Grid, heatmap::Heatmap, room::room_type};
+
+    #[test]
+    fn test_heatmap_colours() {
+        let mut traversal_info = Grid::new(2, 1);
+        traversal_info[(0, 0)] = Some(TraversalInfo {
+            depth: 4,
+            cost: 4,
+            entrance: (0, 0),
+            floor: 0,
+            row: 0,
+            col: 0,
+            room_info: room_type::CROSSING,
+        });
+        let heatmap = Heatmap {
+            traversal_info: &traversal_info,
+            max_depth: 8,
+        };
+        let rgb = |c: Color| (c.r, c.g, c.b);
+        assert_eq!(rgb(heatmap.depth_colour(0)), rgb(SKYBLUE));
+        assert_eq!(rgb(heatmap.room_colour(0, 0)), rgb(YELLOW));
+        assert_eq!(rgb(heatmap.depth_colour(8)), rgb(RED));
+        assert_eq!(rgb(heatmap.depth_colour(20)), rgb(RED));
+        assert_eq!(rgb(heatmap.room_colour(0, 1)), rgb(DARKGRAY));
+
+        let flat = Heatmap {
+            traversal_info: &traversal_info,
+            max_depth: 0,
+        };
+        assert_eq!(rgb(flat.room_colour(0, 0)), rgb(SKYBLUE));
+    }
+}
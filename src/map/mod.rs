@@ -1,14 +1,78 @@
 use std::{
-    collections::{HashSet, VecDeque},
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
     fmt,
+    ops::Range,
 };
 
 use macroquad::prelude::*;
 
+pub mod ascii;
+pub mod catalogue;
+pub mod diagnostics;
+pub mod grid;
+pub mod heatmap;
+pub mod path;
+pub mod repair;
 pub mod room;
 
-pub struct MapLevel<const W: usize, const H: usize> {
-    pub rooms: [[Option<room::SimpleRoomDrawInfo>; W]; H],
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapLevel {
+    pub rooms: grid::Grid<room::SimpleRoomDrawInfo>,
+}
+
+/// A stack of floors, floor 0 is the one holding the entrance and each following floor is one further down
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dungeon {
+    pub floors: Vec<MapLevel>,
+}
+
+// (floor, row, col)
+pub type Cell = (usize, usize, usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Left,
+        Direction::Right,
+        Direction::Top,
+        Direction::Bottom,
+    ];
+
+    pub fn opposite(self) -> Direction {
+        return match self {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::Top => Direction::Bottom,
+            Direction::Bottom => Direction::Top,
+        };
+    }
+
+    /// (row, col) step towards this side
+    pub fn offset(self) -> (isize, isize) {
+        return match self {
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+            Direction::Top => (-1, 0),
+            Direction::Bottom => (1, 0),
+        };
+    }
+
+    /// The side of `from` that `to` is on, None unless they are next to each other
+    pub fn between(from: (usize, usize), to: (usize, usize)) -> Option<Direction> {
+        let step = (
+            to.0 as isize - from.0 as isize,
+            to.1 as isize - from.1 as isize,
+        );
+        return Direction::ALL.into_iter().find(|x| x.offset() == step);
+    }
 }
 
 // from_room, to_room
@@ -38,145 +102,590 @@ const TRAVERSAL_DIRS: [(RoomPredicate, isize, isize); 4] = [
     ),
 ];
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct TraversalInfo {
     pub depth: i32,
+    // sum of the traversal cost of every room entered on the way here, the starting room is free
+    pub cost: u32,
+    // the (row, col) of the start this was reached from, for the game's traversals the closest top floor entrance
+    pub entrance: (usize, usize),
+    pub floor: usize,
     pub row: isize,
     pub col: isize,
     pub room_info: room::SimpleRoomDrawInfo,
 }
 
+/// How the depth of every cell in a dungeon is worked out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TraversalMode {
+    // fewest rooms from an entrance
+    #[default]
+    Breadth,
+    // cheapest total traversal cost from an entrance
+    Weighted,
+}
+
+impl TraversalMode {
+    /// What the mode minimises, lower is closer. Ties on the main measure go to the lower other one and then to the
+    /// first entrance by position, so every cell has exactly one best [TraversalInfo] however it was worked out
+    pub fn key(self, ti: &TraversalInfo) -> (u64, u64, (usize, usize)) {
+        let (depth, cost) = (ti.depth.max(0) as u64, ti.cost as u64);
+        return match self {
+            TraversalMode::Breadth => (depth, cost, ti.entrance),
+            TraversalMode::Weighted => (cost, depth, ti.entrance),
+        };
+    }
+}
+
+/// Why a traversal couldn't be carried out
+#[derive(Clone, Debug, PartialEq)]
+pub enum TraversalError {
+    OutOfBounds {
+        floor: usize,
+        row: usize,
+        col: usize,
+    },
+    EmptyStart {
+        floor: usize,
+        row: usize,
+        col: usize,
+    },
+    // the rooms still waiting to be visited when the traversal gave up
+    VisitLimitExceeded {
+        queue: Vec<TraversalInfo>,
+    },
+}
+
+impl fmt::Display for TraversalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraversalError::OutOfBounds { floor, row, col } => write!(
+                f,
+                "traversal started outside the dungeon at floor {floor}, row {row}, col {col}"
+            ),
+            TraversalError::EmptyStart { floor, row, col } => write!(
+                f,
+                "traversal started from an empty cell at floor {floor}, row {row}, col {col}"
+            ),
+            TraversalError::VisitLimitExceeded { queue } => {
+                write!(
+                    f,
+                    "traversal looped too many times, current queue: {queue:?}"
+                )
+            }
+        }
+    }
+}
+
 impl fmt::Display for TraversalInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "depth: {}, row: {}, col: {}",
-            self.depth, self.row, self.col
+            "depth: {}, cost: {}, entrance: {:?}, floor: {}, row: {}, col: {}",
+            self.depth, self.cost, self.entrance, self.floor, self.row, self.col
         )
     }
 }
 
-pub struct MapLevelDrawingCoords<const W: usize, const H: usize> {
-    coords: [[Vec4; W]; H],
+/// Where a level was drawn on screen, for finding the room under a point
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapLevelDrawingCoords {
+    top_left: Vec2,
+    // pixels per room
+    scale: f32,
+    width: usize,
+    height: usize,
 }
 
-impl<const W: usize, const H: usize> MapLevel<W, H> {
-    const MAX_TRAVERSAL_VISITS: usize = W * H;
+impl MapLevel {
+    pub fn new(width: usize, height: usize) -> Self {
+        return MapLevel {
+            rooms: grid::Grid::new(width, height),
+        };
+    }
+
+    pub fn width(&self) -> usize {
+        return self.rooms.width();
+    }
+
+    pub fn height(&self) -> usize {
+        return self.rooms.height();
+    }
+
+    /// The room in the cell next to (`row`, `col`) on `side`, None when that is off the map or empty
+    fn neighbour(
+        &self,
+        row: usize,
+        col: usize,
+        side: Direction,
+    ) -> Option<room::SimpleRoomDrawInfo> {
+        let (row_add, col_add) = side.offset();
+        return self.neighbour_at(row, col, row_add, col_add);
+    }
+
+    fn neighbour_at(
+        &self,
+        row: usize,
+        col: usize,
+        row_add: isize,
+        col_add: isize,
+    ) -> Option<room::SimpleRoomDrawInfo> {
+        let (new_row, new_col) = (row as isize + row_add, col as isize + col_add);
+        if !self.rooms.in_bounds(new_row, new_col) {
+            return None;
+        }
+        return self.rooms[(new_row as usize, new_col as usize)];
+    }
+
+    /// How many neighbours `room` would be joined to if it were built at (`row`, `col`), by the same rules traversal
+    /// follows
+    pub fn matched_exits(&self, row: usize, col: usize, room: room::SimpleRoomDrawInfo) -> usize {
+        return TRAVERSAL_DIRS
+            .iter()
+            .filter(|(predicate, row_add, col_add)| {
+                self.neighbour_at(row, col, *row_add, *col_add)
+                    .is_some_and(|x| predicate(room, x))
+            })
+            .count();
+    }
 
-    pub fn draw(&self, top_left: Vec2, scale: f32) -> MapLevelDrawingCoords<W, H> {
+    /// `room` turned whichever way joins it to the most neighbours at (`row`, `col`). Ties keep the turn closest to
+    /// the one it was given, clockwise
+    pub fn auto_orient(
+        &self,
+        row: usize,
+        col: usize,
+        room: room::SimpleRoomDrawInfo,
+    ) -> room::SimpleRoomDrawInfo {
+        let mut best = room;
+        let mut turned = room;
+        for _ in 0..3 {
+            turned = turned.rotate_right();
+            if self.matched_exits(row, col, turned) > self.matched_exits(row, col, best) {
+                best = turned;
+            }
+        }
+        return best;
+    }
+
+    /// The first of `candidates`, turned to fit, with an exit towards every neighbour of (`row`, `col`) that has one
+    /// facing it and no others. None when no neighbour faces the cell or no candidate fits
+    pub fn auto_connect(
+        &self,
+        row: usize,
+        col: usize,
+        candidates: impl IntoIterator<Item = room::SimpleRoomDrawInfo>,
+    ) -> Option<room::SimpleRoomDrawInfo> {
+        let wanted = |side: Direction| {
+            self.neighbour(row, col, side)
+                .is_some_and(|x| x.has_exit(side.opposite()))
+        };
+        if !Direction::ALL.into_iter().any(wanted) {
+            return None;
+        }
+        return room::with_exits(candidates, wanted);
+    }
+
+    fn max_traversal_visits(&self) -> usize {
+        return self.width() * self.height();
+    }
+
+    /// Draws every room, tinted by depth when given a heatmap
+    pub fn draw(
+        &self,
+        top_left: Vec2,
+        scale: f32,
+        heatmap: Option<&heatmap::Heatmap>,
+    ) -> MapLevelDrawingCoords {
         draw_rectangle(
             top_left.x,
             top_left.y,
-            scale * W as f32,
-            scale * H as f32,
+            scale * self.width() as f32,
+            scale * self.height() as f32,
             room::ROOM_BACKGROUND.with_alpha(0.8),
         );
-        let mut y = top_left.y;
-        let mut coords: [[Vec4; W]; H] = [[Vec4::ZERO; W]; H];
-        for (row, room_row) in self.rooms.iter().enumerate() {
-            let mut x = top_left.x;
-            for (col, room) in room_row.iter().enumerate() {
-                if room.is_some() {
-                    room.unwrap().draw(Vec2::new(x, y), scale);
-                }
-                coords[row][col] = Vec4 {
-                    x,
-                    y,
-                    z: x + scale,
-                    w: y + scale,
+        let coords = MapLevelDrawingCoords {
+            top_left,
+            scale,
+            width: self.width(),
+            height: self.height(),
+        };
+        // only the rooms that end up on screen are drawn, so big maps cost no more than the part being looked at
+        let (rows, cols) =
+            coords.visible_cells(Rect::new(0.0, 0.0, screen_width(), screen_height()));
+        for row in rows {
+            for col in cols.clone() {
+                let Some(room) = self.rooms[(row, col)] else {
+                    continue;
                 };
-                x += scale;
+                let bounds = coords.cell_bounds(row, col);
+                room.draw(Vec2::new(bounds.x, bounds.y), scale);
+                if let Some(heatmap) = heatmap {
+                    draw_rectangle(
+                        bounds.x,
+                        bounds.y,
+                        scale,
+                        scale,
+                        heatmap.room_colour(row, col),
+                    );
+                }
             }
-            y += scale;
         }
-        return MapLevelDrawingCoords { coords };
+        return coords;
+    }
+}
+
+impl Dungeon {
+    pub fn new(width: usize, height: usize) -> Self {
+        return Dungeon {
+            floors: vec![MapLevel::new(width, height)],
+        };
+    }
+
+    /// Adds an empty floor below the current deepest floor, sized to match the floors above it
+    pub fn add_floor(&mut self) {
+        let top = &self.floors[0];
+        self.floors.push(MapLevel::new(top.width(), top.height()));
+    }
+
+    /// Expands every floor together so rooms stay lined up with the stairs above and below them
+    pub fn expand(&mut self, side: Direction, count: usize) {
+        for floor in self.floors.iter_mut() {
+            floor.rooms.expand(side, count);
+        }
+    }
+
+    /// The (floor, row, col) of every room directly connected to the room at the given position
+    pub fn neighbours(&self, floor: usize, row: usize, col: usize) -> Vec<(usize, usize, usize)> {
+        return match self.floors[floor].rooms[(row, col)] {
+            Some(room_info) => connected_rooms(
+                &self.floors,
+                &TraversalInfo {
+                    depth: 0,
+                    cost: 0,
+                    entrance: (row, col),
+                    floor,
+                    row: row as isize,
+                    col: col as isize,
+                    room_info,
+                },
+            )
+            .into_iter()
+            .map(|x| (x.floor, x.row as usize, x.col as usize))
+            .collect(),
+            None => Vec::new(),
+        };
     }
 
-    pub fn breadth_traverse<TraversalFn>(
+    /// Traverses from every top floor entrance in a single pass, each room is visited once with the way to it from
+    /// its closest entrance. Entrances that are outside the dungeon or empty are skipped and reported along with
+    /// anything that stopped the traversal
+    pub fn traverse_entrances<TraversalFn>(
         &self,
-        start_row: usize,
-        start_col: usize,
-        mut visitor: TraversalFn,
-    ) where
+        entrance_rowcols: &[(usize, usize)],
+        mode: TraversalMode,
+        visitor: TraversalFn,
+    ) -> Vec<TraversalError>
+    where
         TraversalFn: FnMut(TraversalInfo),
     {
-        if start_col >= W || start_row >= H {
-            panic!("Start was outside map bounds! {start_col} -> [0,{W}), {start_row} -> [0, {H})")
+        let mut errors = Vec::new();
+        let mut starts = Vec::new();
+        for (row, col) in entrance_rowcols.iter() {
+            match start_room(&self.floors, 0, *row, *col) {
+                Ok(start) => starts.push(start),
+                Err(e) => errors.push(e),
+            }
         }
-        let mut traversal_queue: VecDeque<TraversalInfo> = VecDeque::new();
-        let mut already_visited: HashSet<(isize, isize)> = HashSet::new();
-
-        self.rooms[start_row][start_col].inspect(|x| {
-            traversal_queue.push_back(TraversalInfo {
-                depth: 0,
-                col: start_col as isize,
-                row: start_row as isize,
-                room_info: *x,
-            })
+        let result = match mode {
+            TraversalMode::Breadth => traverse_floors(&self.floors, starts, visitor),
+            TraversalMode::Weighted => weighted_traverse_floors(&self.floors, starts, visitor),
+        };
+        errors.extend(result.err());
+        return errors;
+    }
+}
+
+/// The floor a stair room leads to, if the room there has the stairs coming back
+fn linked_floor(floors: &[MapLevel], current: &TraversalInfo) -> Option<usize> {
+    let (stairs, new_floor) = match current.room_info.stairs? {
+        room::Stairs::Down => (room::Stairs::Up, current.floor + 1),
+        room::Stairs::Up => (room::Stairs::Down, current.floor.checked_sub(1)?),
+    };
+    let level = floors.get(new_floor)?;
+    if !level.rooms.in_bounds(current.row, current.col) {
+        return None;
+    }
+    return level.rooms[(current.row as usize, current.col as usize)]
+        .filter(|x| x.stairs == Some(stairs))
+        .map(|_| new_floor);
+}
+
+/// Every room that can be moved to in one step from `current`, each one level deeper than `current`
+fn connected_rooms(floors: &[MapLevel], current: &TraversalInfo) -> Vec<TraversalInfo> {
+    let level = &floors[current.floor];
+    let mut result = Vec::new();
+    for (predicate, row_add, col_add) in TRAVERSAL_DIRS.iter() {
+        let new_row = current.row + row_add;
+        let new_col = current.col + col_add;
+        if level.rooms.in_bounds(new_row, new_col) {
+            level.rooms[(new_row as usize, new_col as usize)].inspect(|x| {
+                if predicate(current.room_info, *x) {
+                    result.push(TraversalInfo {
+                        depth: current.depth + 1,
+                        cost: current.cost + x.traversal_cost,
+                        entrance: current.entrance,
+                        floor: current.floor,
+                        row: new_row,
+                        col: new_col,
+                        room_info: *x,
+                    });
+                }
+            });
+        }
+    }
+    if let Some(new_floor) = linked_floor(floors, current) {
+        let room_info = floors[new_floor].rooms[(current.row as usize, current.col as usize)]
+            .expect("Linked stairs were unexpectedly empty");
+        result.push(TraversalInfo {
+            depth: current.depth + 1,
+            cost: current.cost + room_info.traversal_cost,
+            floor: new_floor,
+            room_info,
+            ..*current
         });
-        already_visited.insert((start_row as isize, start_col as isize));
+    }
+    return result;
+}
+
+fn start_room(
+    floors: &[MapLevel],
+    start_floor: usize,
+    start_row: usize,
+    start_col: usize,
+) -> Result<TraversalInfo, TraversalError> {
+    let (floor, row, col) = (start_floor, start_row, start_col);
+    let start_level = floors
+        .get(start_floor)
+        .filter(|x| x.rooms.in_bounds(start_row as isize, start_col as isize))
+        .ok_or(TraversalError::OutOfBounds { floor, row, col })?;
+    return start_level.rooms[(start_row, start_col)]
+        .map(|x| TraversalInfo {
+            depth: 0,
+            cost: 0,
+            entrance: (start_row, start_col),
+            floor: start_floor,
+            col: start_col as isize,
+            row: start_row as isize,
+            room_info: x,
+        })
+        .ok_or(TraversalError::EmptyStart { floor, row, col });
+}
 
-        let mut traversal_visit_count = 0;
+/// Dijkstra's algorithm over the same connections as [traverse_floors], rooms are visited in order of increasing
+/// cost and each one is reached by its cheapest path
+fn weighted_traverse_floors<TraversalFn>(
+    floors: &[MapLevel],
+    starts: Vec<TraversalInfo>,
+    mut visitor: TraversalFn,
+) -> Result<(), TraversalError>
+where
+    TraversalFn: FnMut(TraversalInfo),
+{
+    let mode = TraversalMode::Weighted;
+    // key, floor, row, col keeps the heap ordered by cost and deterministic between equal costs
+    let mut frontier = BinaryHeap::new();
+    let mut best: HashMap<(usize, isize, isize), TraversalInfo> = HashMap::new();
+    let mut finished: HashSet<(usize, isize, isize)> = HashSet::new();
 
-        while !traversal_queue.is_empty() {
-            if traversal_visit_count > Self::MAX_TRAVERSAL_VISITS {
-                panic!(
-                    "Traversal looped too many times, current queue: {:?}",
-                    traversal_queue
-                );
+    for start in starts {
+        let key = (start.floor, start.row, start.col);
+        if best
+            .get(&key)
+            .is_none_or(|x| mode.key(&start) < mode.key(x))
+        {
+            best.insert(key, start);
+            frontier.push(Reverse((
+                mode.key(&start),
+                start.floor,
+                start.row,
+                start.col,
+            )));
+        }
+    }
+
+    let max_traversal_visits: usize = floors.iter().map(|x| x.max_traversal_visits()).sum();
+
+    while let Some(Reverse((_, floor, row, col))) = frontier.pop() {
+        // a cheaper path to the room has already been handled
+        if !finished.insert((floor, row, col)) {
+            continue;
+        }
+        if finished.len() > max_traversal_visits {
+            return Err(TraversalError::VisitLimitExceeded {
+                queue: frontier
+                    .into_sorted_vec()
+                    .into_iter()
+                    .rev()
+                    .map(|Reverse((_, floor, row, col))| best[&(floor, row, col)])
+                    .collect(),
+            });
+        }
+        let current = best[&(floor, row, col)];
+        for next in connected_rooms(floors, &current) {
+            let key = (next.floor, next.row, next.col);
+            if finished.contains(&key)
+                || best
+                    .get(&key)
+                    .is_some_and(|x| mode.key(x) <= mode.key(&next))
+            {
+                continue;
             }
-            traversal_visit_count += 1;
-            let current = traversal_queue
-                .pop_front()
-                .expect("Queue was unexpectedly empty");
-            for (predicate, row_add, col_add) in TRAVERSAL_DIRS.iter() {
-                let new_row = current.row + row_add;
-                let new_col = current.col + col_add;
-                if !already_visited.contains(&(new_row, new_col))
-                    && new_row >= 0
-                    && new_row < H as isize
-                    && new_col >= 0
-                    && new_col < W as isize
-                {
-                    self.rooms[new_row as usize][new_col as usize].inspect(|x| {
-                        if predicate(current.room_info, *x) {
-                            traversal_queue.push_back(TraversalInfo {
-                                depth: current.depth + 1,
-                                row: new_row,
-                                col: new_col,
-                                room_info: *x,
-                            });
-                            already_visited.insert((new_row, new_col));
-                        }
-                    });
+            best.insert(key, next);
+            frontier.push(Reverse((mode.key(&next), next.floor, next.row, next.col)));
+        }
+        visitor(current);
+    }
+    return Ok(());
+}
+
+/// Breadth first search from every start at once, all of them at depth 0
+fn traverse_floors<TraversalFn>(
+    floors: &[MapLevel],
+    starts: Vec<TraversalInfo>,
+    mut visitor: TraversalFn,
+) -> Result<(), TraversalError>
+where
+    TraversalFn: FnMut(TraversalInfo),
+{
+    let mode = TraversalMode::Breadth;
+    let mut traversal_queue: VecDeque<(usize, isize, isize)> = VecDeque::new();
+    // every room queued so far, with the best of the shortest ways found to it
+    let mut discovered: HashMap<(usize, isize, isize), TraversalInfo> = HashMap::new();
+
+    for start in starts {
+        let key = (start.floor, start.row, start.col);
+        match discovered.get_mut(&key) {
+            Some(existing) => {
+                if mode.key(&start) < mode.key(existing) {
+                    *existing = start;
                 }
             }
-            visitor(current);
+            None => {
+                discovered.insert(key, start);
+                traversal_queue.push_back(key);
+            }
         }
     }
+
+    let max_traversal_visits: usize = floors.iter().map(|x| x.max_traversal_visits()).sum();
+    let mut traversal_visit_count = 0;
+
+    while !traversal_queue.is_empty() {
+        if traversal_visit_count > max_traversal_visits {
+            return Err(TraversalError::VisitLimitExceeded {
+                queue: traversal_queue.iter().map(|x| discovered[x]).collect(),
+            });
+        }
+        traversal_visit_count += 1;
+        let current = discovered[&traversal_queue
+            .pop_front()
+            .expect("Queue was unexpectedly empty")];
+        for next in connected_rooms(floors, &current) {
+            let key = (next.floor, next.row, next.col);
+            match discovered.get_mut(&key) {
+                // rooms queued at the same depth are still waiting their turn, so a better way to them can replace
+                // the one found first. Rooms that were already visited are never beaten by a deeper way
+                Some(existing) => {
+                    if mode.key(&next) < mode.key(existing) {
+                        *existing = next;
+                    }
+                }
+                None => {
+                    discovered.insert(key, next);
+                    traversal_queue.push_back(key);
+                }
+            }
+        }
+        visitor(current);
+    }
+    return Ok(());
 }
 
-impl<const W: usize, const H: usize> MapLevelDrawingCoords<W, H> {
+impl MapLevelDrawingCoords {
+    /// The row and column of the cell under `point`. Edges belong to the cell right of or below them, so every point
+    /// on the map is in exactly one cell
+    pub fn cell_at(&self, point: Vec2) -> Option<(usize, usize)> {
+        let cell = ((point - self.top_left) / self.scale).floor();
+        if cell.x < 0.0 || cell.y < 0.0 {
+            return None;
+        }
+        let (row, col) = (cell.y as usize, cell.x as usize);
+        if row >= self.height || col >= self.width {
+            return None;
+        }
+        return Some((row, col));
+    }
+
+    /// Screen bounds of a cell as left, top, right and bottom
+    pub fn cell_bounds(&self, row: usize, col: usize) -> Vec4 {
+        let x = self.top_left.x + col as f32 * self.scale;
+        let y = self.top_left.y + row as f32 * self.scale;
+        return Vec4::new(x, y, x + self.scale, y + self.scale);
+    }
+
+    /// The rows and columns of every cell at least partly inside `view`
+    pub fn visible_cells(&self, view: Rect) -> (Range<usize>, Range<usize>) {
+        let span = |from: f32, to: f32, origin: f32, count: usize| {
+            let first = ((from - origin) / self.scale).floor().max(0.0) as usize;
+            let end = ((to - origin) / self.scale).ceil().max(0.0) as usize;
+            return first.min(count)..end.min(count);
+        };
+        return (
+            span(view.y, view.y + view.h, self.top_left.y, self.height),
+            span(view.x, view.x + view.w, self.top_left.x, self.width),
+        );
+    }
+
     pub fn get_room(
         &self,
-        level: &MapLevel<W, H>,
+        level: &MapLevel,
         point: Vec2,
     ) -> Option<(Option<room::SimpleRoomDrawInfo>, Vec4, (usize, usize))> {
-        for (row, coord_row) in self.coords.iter().enumerate() {
-            for (col, coord) in coord_row.iter().enumerate() {
-                if point.x > coord.x && point.y > coord.y && point.x < coord.z && point.y < coord.w
-                {
-                    return Some((level.rooms[row][col], *coord, (row, col)));
-                }
-            }
-        }
-        return None;
+        let (row, col) = self.cell_at(point)?;
+        return Some((
+            level.rooms[(row, col)],
+            self.cell_bounds(row, col),
+            (row, col),
+        ));
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::map::{MapLevel, TraversalInfo, room::SimpleRoomDrawInfo, room::room_type};
+    use crate::map::catalogue::RoomCatalogue;
+    use crate::map::{
+        Direction, Dungeon, MapLevel, MapLevelDrawingCoords, TraversalError, TraversalInfo,
+        TraversalMode, grid::Grid, room::SimpleRoomDrawInfo, room::room_type,
+    };
+    use crate::resources::Resources;
+    use macroquad::prelude::*;
+
+    /// Traverses a lone level from a single start the same way the game traverses from its entrances
+    fn traverse_level<TraversalFn>(
+        map: &MapLevel,
+        start: (usize, usize),
+        mode: TraversalMode,
+        visitor: TraversalFn,
+    ) -> Vec<TraversalError>
+    where
+        TraversalFn: FnMut(TraversalInfo),
+    {
+        let dungeon = Dungeon {
+            floors: vec![map.clone()],
+        };
+        return dungeon.traverse_entrances(&[start], mode, visitor);
+    }
 
     fn count_some_2d<const W: usize, const H: usize, T>(array2d: [[Option<T>; W]; H]) -> usize {
         array2d
@@ -210,6 +719,12 @@ mod tests {
             top_exit: false,
             bottom_exit: false,
             symbol: None,
+            stairs: None,
+            cost: Resources::ZERO,
+            traversal_cost: 1,
+            colour: WHITE,
+            production: Resources::ZERO,
+            indestructible: false,
         };
 
         assert!(!base_room.rotate_left().left_exit);
@@ -231,14 +746,15 @@ mod tests {
         assert!(right_room.left_exit);
 
         let map = MapLevel {
-            rooms: [[Some(left_room), Some(center_hall), Some(right_room)]],
+            rooms: Grid::from([[Some(left_room), Some(center_hall), Some(right_room)]]),
         };
 
         let mut traversal_result: [[Option<TraversalInfo>; 3]; 1] = [[None; 3]];
 
-        map.breadth_traverse(0, 0, |ti| {
+        let errors = traverse_level(&map, (0, 0), TraversalMode::Breadth, |ti| {
             traversal_result[ti.row as usize][ti.col as usize] = Some(ti);
         });
+        assert!(errors.is_empty());
 
         for ti in traversal_result {
             assert!(!ti.is_empty())
@@ -269,14 +785,15 @@ mod tests {
         assert!(!right_room.left_exit);
 
         let map = MapLevel {
-            rooms: [[Some(left_room), Some(center_hall), Some(right_room)]],
+            rooms: Grid::from([[Some(left_room), Some(center_hall), Some(right_room)]]),
         };
 
         let mut traversal_result: [[Option<TraversalInfo>; 3]; 1] = [[None; 3]];
 
-        map.breadth_traverse(0, 0, |ti| {
+        let errors = traverse_level(&map, (0, 0), TraversalMode::Breadth, |ti| {
             traversal_result[ti.row as usize][ti.col as usize] = Some(ti);
         });
+        assert!(errors.is_empty());
 
         for ti in traversal_result {
             assert!(!ti.is_empty())
@@ -293,9 +810,10 @@ mod tests {
 
         traversal_result = [[None; 3]];
 
-        map.breadth_traverse(0, 2, |ti| {
+        let errors = traverse_level(&map, (0, 2), TraversalMode::Breadth, |ti| {
             traversal_result[ti.row as usize][ti.col as usize] = Some(ti);
         });
+        assert!(errors.is_empty());
 
         let right_traversal = traversal_result[0][2].unwrap();
 
@@ -312,17 +830,18 @@ mod tests {
         let enter_left_up = enter_down_left.rotate_right();
 
         let map = MapLevel {
-            rooms: [
+            rooms: Grid::from([
                 [Some(enter_down_right), Some(enter_down_left)],
                 [Some(enter_up_right), Some(enter_left_up)],
-            ],
+            ]),
         };
 
         let mut traversal_result: [[Option<TraversalInfo>; 2]; 2] = [[None; 2]; 2];
 
-        map.breadth_traverse(0, 0, |ti| {
+        let errors = traverse_level(&map, (0, 0), TraversalMode::Breadth, |ti| {
             traversal_result[ti.row as usize][ti.col as usize] = Some(ti);
         });
+        assert!(errors.is_empty());
 
         assert_eq!(count_some_2d(traversal_result), 4);
         assert_eq!(traversal_result[0][0].unwrap().depth, 0);
@@ -339,7 +858,7 @@ mod tests {
         let enter_left_up = enter_down_left.rotate_right();
 
         let map = MapLevel {
-            rooms: [
+            rooms: Grid::from([
                 [
                     Some(enter_down_right),
                     Some(room_type::HALL.rotate_right()),
@@ -350,14 +869,15 @@ mod tests {
                     Some(enter_up_right),
                     Some(enter_left_up),
                 ],
-            ],
+            ]),
         };
 
         let mut traversal_result: [[Option<TraversalInfo>; 3]; 2] = [[None; 3]; 2];
 
-        map.breadth_traverse(0, 0, |ti| {
+        let errors = traverse_level(&map, (0, 0), TraversalMode::Breadth, |ti| {
             traversal_result[ti.row as usize][ti.col as usize] = Some(ti);
         });
+        assert!(errors.is_empty());
 
         assert_eq!(
             count_some_2d(traversal_result),
@@ -384,18 +904,19 @@ mod tests {
         assert!(right_crossing.left_exit);
 
         let map = MapLevel {
-            rooms: [
+            rooms: Grid::from([
                 [Some(left_room), Some(center_hall), Some(right_crossing)],
                 [None; 3],
-            ],
+            ]),
         };
 
         let mut traversal_result: [[Option<TraversalInfo>; 3]; 2] = [[None; 3]; 2];
 
         // shouldn't panic even though right_hall opens to the outside of the map and towards a non-room
-        map.breadth_traverse(0, 0, |ti| {
+        let errors = traverse_level(&map, (0, 0), TraversalMode::Breadth, |ti| {
             traversal_result[ti.row as usize][ti.col as usize] = Some(ti);
         });
+        assert!(errors.is_empty());
 
         for ti in traversal_result {
             assert!(!ti.is_empty())
@@ -412,4 +933,197 @@ mod tests {
         assert_eq!(right_crossing, right_traversal.room_info);
         assert_eq!(right_traversal.depth, 2);
     }
+
+    #[test]
+    fn test_runtime_sized_level() {
+        let mut map = MapLevel::new(4, 2);
+        assert_eq!(map.width(), 4);
+        assert_eq!(map.height(), 2);
+        assert_eq!(map.rooms.iter_some().count(), 0);
+
+        map.rooms[(1, 3)] = Some(room_type::DEAD_END);
+        map.rooms[(0, 3)] = Some(room_type::HALL);
+
+        let mut visited = Vec::new();
+        let errors = traverse_level(&map, (1, 3), TraversalMode::Breadth, |ti| {
+            visited.push((ti.row, ti.col, ti.depth))
+        });
+        assert!(errors.is_empty());
+
+        assert_eq!(visited, vec![(1, 3, 0), (0, 3, 1)]);
+    }
+
+    #[test]
+    fn test_traversal_stairs() {
+        let mut dungeon = Dungeon::new(2, 1);
+        dungeon.add_floor();
+        dungeon.add_floor();
+        dungeon.floors[0].rooms[(0, 0)] = Some(room_type::DEAD_END.rotate_right());
+        dungeon.floors[0].rooms[(0, 1)] = Some(room_type::STAIRS_DOWN.rotate_left());
+        dungeon.floors[1].rooms[(0, 1)] = Some(room_type::STAIRS_UP.rotate_left());
+        dungeon.floors[1].rooms[(0, 0)] = Some(room_type::STAIRS_DOWN.rotate_right());
+        // stairs that lead down to a room without stairs going back up don't connect
+        dungeon.floors[2].rooms[(0, 0)] = Some(room_type::CROSSING);
+
+        let mut visited = Vec::new();
+        let errors = dungeon.traverse_entrances(&[(0, 0)], TraversalMode::Breadth, |ti| {
+            visited.push((ti.floor, ti.row, ti.col, ti.depth))
+        });
+        assert!(errors.is_empty());
+        assert_eq!(
+            visited,
+            vec![(0, 0, 0, 0), (0, 0, 1, 1), (1, 0, 1, 2), (1, 0, 0, 3)]
+        );
+
+        // stairs work in both directions
+        let neighbours = dungeon.neighbours(1, 0, 1);
+        assert!(neighbours.contains(&(0, 0, 1)));
+        assert!(neighbours.contains(&(1, 0, 0)));
+        assert!(dungeon.neighbours(1, 0, 0).iter().all(|x| x.0 == 1));
+    }
+
+    #[test]
+    fn test_traversal_errors() {
+        let mut dungeon = Dungeon::new(2, 1);
+        dungeon.floors[0].rooms[(0, 0)] = Some(room_type::CROSSING);
+        let mut visits = 0;
+
+        assert_eq!(
+            dungeon.traverse_entrances(&[(0, 1)], TraversalMode::Breadth, |_| visits += 1),
+            vec![TraversalError::EmptyStart {
+                floor: 0,
+                row: 0,
+                col: 1
+            }]
+        );
+        assert_eq!(
+            dungeon.traverse_entrances(&[(1, 0)], TraversalMode::Weighted, |_| visits += 1),
+            vec![TraversalError::OutOfBounds {
+                floor: 0,
+                row: 1,
+                col: 0
+            }]
+        );
+        assert!(matches!(
+            traverse_level(&dungeon.floors[0], (0, 2), TraversalMode::Breadth, |_| {
+                visits += 1
+            })[..],
+            [TraversalError::OutOfBounds { .. }]
+        ));
+        assert_eq!(visits, 0);
+
+        // a bad entrance is reported without stopping the traversal from the good ones
+        let errors =
+            dungeon.traverse_entrances(&[(0, 1), (0, 0)], TraversalMode::Breadth, |_| visits += 1);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(visits, 1);
+    }
+
+    #[test]
+    fn test_weighted_traversal() {
+        // two ways around a loop, the direct one along the top goes through an expensive trap and the other through
+        // a hall
+        let mut map = MapLevel::from_ascii(
+            "
++-++-++-+
+|       |
++ ++-++ +
++ ++-++ +
+|       |
++-++-++-+
+",
+            &RoomCatalogue::built_in(),
+        )
+        .unwrap();
+        map.rooms[(0, 1)].as_mut().unwrap().traversal_cost = 10;
+
+        let mut visited = Vec::new();
+        let errors = traverse_level(&map, (0, 0), TraversalMode::Weighted, |ti| {
+            visited.push((ti.row, ti.col, ti.depth, ti.cost))
+        });
+        assert!(errors.is_empty());
+        assert_eq!(
+            visited,
+            vec![
+                (0, 0, 0, 0),
+                (1, 0, 1, 1),
+                (1, 1, 2, 3),
+                (1, 2, 3, 4),
+                (0, 2, 4, 5),
+                (0, 1, 1, 10)
+            ]
+        );
+
+        // breadth first ignores the cost when picking a path but still adds it up
+        visited.clear();
+        let errors = traverse_level(&map, (0, 0), TraversalMode::Breadth, |ti| {
+            visited.push((ti.row, ti.col, ti.depth, ti.cost))
+        });
+        assert!(errors.is_empty());
+        assert!(visited.contains(&(0, 2, 2, 11)));
+    }
+
+    #[test]
+    fn test_drawing_coords() {
+        let mut level = MapLevel::new(4, 3);
+        level.rooms[(1, 2)] = Some(room_type::CROSSING);
+        let coords = MapLevelDrawingCoords {
+            top_left: Vec2::new(-50.0, 10.0),
+            scale: 20.0,
+            width: 4,
+            height: 3,
+        };
+        assert_eq!(coords.cell_at(Vec2::new(-50.0, 10.0)), Some((0, 0)));
+        // a shared edge belongs to the room right of it
+        assert_eq!(coords.cell_at(Vec2::new(-10.0, 35.0)), Some((1, 2)));
+        assert_eq!(coords.cell_at(Vec2::new(29.9, 69.9)), Some((2, 3)));
+        assert_eq!(coords.cell_at(Vec2::new(30.0, 20.0)), None);
+        assert_eq!(coords.cell_at(Vec2::new(-50.1, 20.0)), None);
+        assert_eq!(coords.cell_at(Vec2::new(0.0, 70.0)), None);
+
+        let (room, bounds, cell) = coords.get_room(&level, Vec2::new(-5.0, 45.0)).unwrap();
+        assert_eq!(room, Some(room_type::CROSSING));
+        assert_eq!(bounds, Vec4::new(-10.0, 30.0, 10.0, 50.0));
+        assert_eq!(cell, (1, 2));
+
+        // the first two columns are left of the view and the last row is below it
+        assert_eq!(
+            coords.visible_cells(Rect::new(0.0, 0.0, 100.0, 45.0)),
+            (0..2, 2..4)
+        );
+        assert_eq!(
+            coords.visible_cells(Rect::new(200.0, 0.0, 100.0, 100.0)),
+            (0..3, 4..4)
+        );
+    }
+
+    #[test]
+    fn test_auto_tiling() {
+        let exits = |room: SimpleRoomDrawInfo| {
+            return Direction::ALL.map(|x| room.has_exit(x));
+        };
+        // a hall above and one to the left both lead into the middle, the room to the right has no way in
+        let mut level = MapLevel::new(3, 3);
+        level.rooms[(0, 1)] = Some(room_type::HALL);
+        level.rooms[(1, 0)] = Some(room_type::HALL.rotate_right());
+        level.rooms[(1, 2)] = Some(room_type::NO_EXIT);
+
+        assert_eq!(level.matched_exits(1, 1, room_type::L), 1);
+        assert_eq!(level.matched_exits(1, 1, room_type::CROSSING), 2);
+        let oriented = level.auto_orient(1, 1, room_type::L);
+        assert_eq!(exits(oriented), [true, false, true, false]);
+        assert_eq!(level.matched_exits(1, 1, oriented), 2);
+        // turning a dead end left joins no more rooms than it already does, so it stays as it is
+        assert_eq!(
+            level.auto_orient(1, 1, room_type::DEAD_END),
+            room_type::DEAD_END
+        );
+
+        let connected = level.auto_connect(1, 1, room_type::ALL_TYPES).unwrap();
+        assert_eq!(exits(connected), [true, false, true, false]);
+        assert_eq!(connected.cost, room_type::L.cost);
+        // nothing around these leads into them
+        assert_eq!(level.auto_connect(2, 1, room_type::ALL_TYPES), None);
+        assert_eq!(level.auto_connect(2, 2, room_type::ALL_TYPES), None);
+    }
 }
@@ -0,0 +1,175 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use crate::map::{Direction, MapLevel, TraversalInfo, connected_rooms};
+
+/// One room along a [Path]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PathStep {
+    pub row: usize,
+    pub col: usize,
+    // the exit taken out of this room towards the next step, None for the last room
+    pub exit: Option<Direction>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path {
+    // starts with the room the path starts in and ends with its destination
+    pub steps: Vec<PathStep>,
+    // total traversal cost of every room entered, the starting room is free
+    pub cost: u32,
+}
+
+impl MapLevel {
+    /// The cheapest route between two rooms by traversal cost, found with A*. None when either cell is empty or
+    /// outside the level, or when there is no way through
+    pub fn find_path(&self, from: (usize, usize), to: (usize, usize)) -> Option<Path> {
+        let in_bounds =
+            |(row, col): (usize, usize)| self.rooms.in_bounds(row as isize, col as isize);
+        if !in_bounds(from) || !in_bounds(to) || self.rooms[to].is_none() {
+            return None;
+        }
+        let start = TraversalInfo {
+            depth: 0,
+            cost: 0,
+            entrance: from,
+            floor: 0,
+            row: from.0 as isize,
+            col: from.1 as isize,
+            room_info: self.rooms[from]?,
+        };
+        // manhattan distance times the cheapest room never overestimates, which keeps the result optimal
+        let cheapest_room = self
+            .rooms
+            .iter_some()
+            .map(|(_, _, x)| x.traversal_cost)
+            .min()
+            .unwrap_or(0);
+        let estimate = |row: isize, col: isize| {
+            let distance = row.abs_diff(to.0 as isize) + col.abs_diff(to.1 as isize);
+            return distance as u32 * cheapest_room;
+        };
+
+        let levels = std::slice::from_ref(self);
+        // estimated total, cost so far, row, col
+        let mut frontier = BinaryHeap::new();
+        let mut best: HashMap<(isize, isize), TraversalInfo> = HashMap::new();
+        let mut came_from: HashMap<(isize, isize), (isize, isize)> = HashMap::new();
+        let mut finished: HashSet<(isize, isize)> = HashSet::new();
+        frontier.push(Reverse((
+            estimate(start.row, start.col),
+            0,
+            start.row,
+            start.col,
+        )));
+        best.insert((start.row, start.col), start);
+
+        while let Some(Reverse((_, _, row, col))) = frontier.pop() {
+            if (row as usize, col as usize) == to {
+                return Some(collect_path(&came_from, (row, col), best[&(row, col)].cost));
+            }
+            if !finished.insert((row, col)) {
+                continue;
+            }
+            let current = best[&(row, col)];
+            for next in connected_rooms(levels, &current) {
+                let key = (next.row, next.col);
+                if finished.contains(&key) || best.get(&key).is_some_and(|x| x.cost <= next.cost) {
+                    continue;
+                }
+                best.insert(key, next);
+                came_from.insert(key, (row, col));
+                frontier.push(Reverse((
+                    next.cost + estimate(next.row, next.col),
+                    next.cost,
+                    next.row,
+                    next.col,
+                )));
+            }
+        }
+        return None;
+    }
+}
+
+/// Walks `came_from` back from `end` to the start and labels every step with the exit it leaves by
+fn collect_path(
+    came_from: &HashMap<(isize, isize), (isize, isize)>,
+    end: (isize, isize),
+    cost: u32,
+) -> Path {
+    let mut cells = vec![end];
+    while let Some(previous) = came_from.get(&cells[cells.len() - 1]) {
+        cells.push(*previous);
+    }
+    cells.reverse();
+    let cells: Vec<_> = cells
+        .into_iter()
+        .map(|(row, col)| (row as usize, col as usize))
+        .collect();
+    let steps = cells
+        .iter()
+        .enumerate()
+        .map(|(i, &(row, col))| PathStep {
+            row,
+            col,
+            exit: cells.get(i + 1).map(|&next| {
+                return Direction::between((row, col), next)
+                    .expect("Path steps should be next to each other");
+            }),
+        })
+        .collect();
+    return Path { steps, cost };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::{Direction, MapLevel, catalogue::RoomCatalogue, path::PathStep};
+
+    #[test]
+    fn test_find_path() {
+        // the same loop as test_weighted_traversal, plus a room off to the side that nothing connects to
+        let mut map = MapLevel::from_ascii(
+            "
++-++-++-++-+
+|       || |
++ ++-++ ++-+
++ ++-++ +...
+|       |...
++-++-++-+...
+",
+            &RoomCatalogue::built_in(),
+        )
+        .unwrap();
+        map.rooms[(0, 1)].as_mut().unwrap().traversal_cost = 10;
+
+        let path = map.find_path((0, 0), (0, 2)).unwrap();
+        assert_eq!(path.cost, 5);
+        let step = |row, col, exit| PathStep { row, col, exit };
+        assert_eq!(
+            path.steps,
+            vec![
+                step(0, 0, Some(Direction::Bottom)),
+                step(1, 0, Some(Direction::Right)),
+                step(1, 1, Some(Direction::Right)),
+                step(1, 2, Some(Direction::Top)),
+                step(0, 2, None),
+            ]
+        );
+
+        // once the trap is gone the top is the cheaper way
+        map.rooms[(0, 1)].as_mut().unwrap().traversal_cost = 1;
+        let path = map.find_path((0, 0), (0, 2)).unwrap();
+        assert_eq!(path.cost, 2);
+        assert_eq!(path.steps.len(), 3);
+
+        assert_eq!(
+            map.find_path((1, 1), (1, 1)).unwrap().steps,
+            vec![step(1, 1, None)]
+        );
+        assert_eq!(map.find_path((0, 0), (0, 3)), None);
+        assert_eq!(map.find_path((0, 0), (1, 3)), None);
+        assert_eq!(map.find_path((0, 0), (5, 5)), None);
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+};
+
+use crate::map::{
+    Cell, Dungeon, TraversalError, TraversalInfo, TraversalMode, connected_rooms, grid::Grid,
+    start_room,
+};
+
+impl Dungeon {
+    /// Brings `traversal_info` up to date after the room at `changed` was replaced, giving the same result as
+    /// traversing again from every entrance while only revisiting the rooms whose best path could have changed.
+    /// Returns the problems with any of the entrances, like a full traversal would
+    pub fn repair_traversal(
+        &self,
+        traversal_info: &mut [Grid<TraversalInfo>],
+        entrance_rowcols: &[(usize, usize)],
+        mode: TraversalMode,
+        changed: Cell,
+    ) -> Vec<TraversalError> {
+        let affected = self.dependent_cells(traversal_info, mode, changed);
+        for (floor, row, col) in affected.iter() {
+            traversal_info[*floor][(*row, *col)] = None;
+        }
+
+        // the best way into each cleared room from an entrance or a room that kept its info
+        let mut seeds = Vec::new();
+        for (floor, row, col) in affected.iter().copied() {
+            let Some(room_info) = self.floors[floor].rooms[(row, col)] else {
+                continue;
+            };
+            let here = TraversalInfo {
+                depth: 0,
+                cost: 0,
+                entrance: (row, col),
+                floor,
+                row: row as isize,
+                col: col as isize,
+                room_info,
+            };
+            let mut candidates = Vec::new();
+            if floor == 0 && entrance_rowcols.contains(&(row, col)) {
+                candidates.push(here);
+            }
+            for neighbour in connected_rooms(&self.floors, &here) {
+                let neighbour_cell = (neighbour.row as usize, neighbour.col as usize);
+                if let Some(neighbour_info) = traversal_info[neighbour.floor][neighbour_cell] {
+                    candidates.extend(
+                        connected_rooms(&self.floors, &neighbour_info)
+                            .into_iter()
+                            .filter(|x| (x.floor, x.row, x.col) == (floor, here.row, here.col)),
+                    );
+                }
+            }
+            if let Some(best) = candidates.into_iter().min_by_key(|x| mode.key(x)) {
+                seeds.push(best);
+            }
+        }
+
+        // dijkstra from the seeds, which also carries any improvement on to rooms that weren't cleared
+        let mut frontier = BinaryHeap::new();
+        for seed in seeds {
+            traversal_info[seed.floor][(seed.row as usize, seed.col as usize)] = Some(seed);
+            frontier.push(Reverse((mode.key(&seed), seed.floor, seed.row, seed.col)));
+        }
+        while let Some(Reverse((key, floor, row, col))) = frontier.pop() {
+            let Some(current) = traversal_info[floor][(row as usize, col as usize)] else {
+                continue;
+            };
+            // a better way here was found after this one was queued
+            if mode.key(&current) != key {
+                continue;
+            }
+            for next in connected_rooms(&self.floors, &current) {
+                let slot = &mut traversal_info[next.floor][(next.row as usize, next.col as usize)];
+                if slot.is_none_or(|x| mode.key(&next) < mode.key(&x)) {
+                    *slot = Some(next);
+                    frontier.push(Reverse((mode.key(&next), next.floor, next.row, next.col)));
+                }
+            }
+        }
+
+        return entrance_rowcols
+            .iter()
+            .filter_map(|(row, col)| start_room(&self.floors, 0, *row, *col).err())
+            .collect();
+    }
+
+    /// `changed` and every room whose recorded info could have been reached through it, found by following rooms
+    /// whose info is exactly one step on from an affected neighbour's
+    fn dependent_cells(
+        &self,
+        traversal_info: &[Grid<TraversalInfo>],
+        mode: TraversalMode,
+        changed: Cell,
+    ) -> Vec<Cell> {
+        let mut affected = vec![changed];
+        let mut seen: HashSet<Cell> = HashSet::from([changed]);
+        let mut i = 0;
+        while i < affected.len() {
+            let (floor, row, col) = affected[i];
+            i += 1;
+            let Some(current) = traversal_info[floor][(row, col)] else {
+                continue;
+            };
+            // the connections of the changed room are gone, so every adjacent cell is checked rather than only
+            // the connected ones
+            let (row, col) = (row as isize, col as isize);
+            let mut adjacent = vec![
+                (floor, row - 1, col),
+                (floor, row + 1, col),
+                (floor, row, col - 1),
+                (floor, row, col + 1),
+                (floor + 1, row, col),
+            ];
+            if floor > 0 {
+                adjacent.push((floor - 1, row, col));
+            }
+            for (next_floor, next_row, next_col) in adjacent {
+                let Some(floor_info) = traversal_info.get(next_floor) else {
+                    continue;
+                };
+                if !floor_info.in_bounds(next_row, next_col) {
+                    continue;
+                }
+                let cell = (next_floor, next_row as usize, next_col as usize);
+                let Some(next) = floor_info[(cell.1, cell.2)] else {
+                    continue;
+                };
+                let through_current = TraversalInfo {
+                    depth: current.depth + 1,
+                    cost: current.cost + next.room_info.traversal_cost,
+                    entrance: current.entrance,
+                    ..next
+                };
+                if mode.key(&next) == mode.key(&through_current) && seen.insert(cell) {
+                    affected.push(cell);
+                }
+            }
+        }
+        return affected;
+    }
+}
@@ -1,5 +1,14 @@
 use macroquad::prelude::*;
 
+use crate::{map::Direction, resources::Resources};
+
+/// Which neighbouring floor a stair room leads to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stairs {
+    Up,
+    Down,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct SimpleRoomDrawInfo {
     pub left_exit: bool,
@@ -7,14 +16,24 @@ pub struct SimpleRoomDrawInfo {
     pub top_exit: bool,
     pub bottom_exit: bool,
     pub symbol: Option<char>,
+    pub stairs: Option<Stairs>,
+    // what it takes to build the room
+    pub cost: Resources,
+    // how hard the room is to pass through, weighted traversal adds this up for every room entered
+    pub traversal_cost: u32,
+    // what the room is drawn in
+    pub colour: Color,
+    // added to every payout while the room can be reached from an entrance
+    pub production: Resources,
+    // can't be built over or removed, only rotated
+    pub indestructible: bool,
 }
 
 pub const ROOM_BACKGROUND: Color = BLACK;
 
+// rooms are drawn in this unless their type says otherwise
 pub const ROOM_BODY: Color = RED;
 
-pub const ROOM_EXIT: Color = RED;
-
 pub const ROOM_SYMBOL: Color = GRAY;
 
 impl SimpleRoomDrawInfo {
@@ -28,7 +47,7 @@ impl SimpleRoomDrawInfo {
             top_left.y + room_inset,
             scale - exit_offset,
             scale - exit_offset,
-            ROOM_BODY,
+            self.colour,
         );
         if self.bottom_exit {
             draw_rectangle(
@@ -36,7 +55,7 @@ impl SimpleRoomDrawInfo {
                 top_left.y + scale - room_inset,
                 exit_size,
                 room_inset,
-                ROOM_EXIT,
+                self.colour,
             );
         }
         if self.top_exit {
@@ -45,7 +64,7 @@ impl SimpleRoomDrawInfo {
                 top_left.y,
                 exit_size,
                 room_inset,
-                ROOM_EXIT,
+                self.colour,
             );
         }
         if self.right_exit {
@@ -54,7 +73,7 @@ impl SimpleRoomDrawInfo {
                 top_left.y + exit_offset,
                 room_inset,
                 exit_size,
-                ROOM_EXIT,
+                self.colour,
             );
         }
         if self.left_exit {
@@ -63,7 +82,7 @@ impl SimpleRoomDrawInfo {
                 top_left.y + exit_offset,
                 room_inset,
                 exit_size,
-                ROOM_EXIT,
+                self.colour,
             );
         }
         self.symbol.inspect(|sym| {
@@ -78,6 +97,15 @@ impl SimpleRoomDrawInfo {
         });
     }
 
+    pub fn has_exit(&self, side: Direction) -> bool {
+        return match side {
+            Direction::Left => self.left_exit,
+            Direction::Right => self.right_exit,
+            Direction::Top => self.top_exit,
+            Direction::Bottom => self.bottom_exit,
+        };
+    }
+
     pub fn rotate_left(self) -> SimpleRoomDrawInfo {
         return SimpleRoomDrawInfo {
             top_exit: self.right_exit,
@@ -85,6 +113,12 @@ impl SimpleRoomDrawInfo {
             bottom_exit: self.left_exit,
             left_exit: self.top_exit,
             symbol: self.symbol,
+            stairs: self.stairs,
+            cost: self.cost,
+            traversal_cost: self.traversal_cost,
+            colour: self.colour,
+            production: self.production,
+            indestructible: self.indestructible,
         };
     }
 
@@ -95,12 +129,42 @@ impl SimpleRoomDrawInfo {
             left_exit: self.bottom_exit,
             top_exit: self.left_exit,
             symbol: self.symbol,
+            stairs: self.stairs,
+            cost: self.cost,
+            traversal_cost: self.traversal_cost,
+            colour: self.colour,
+            production: self.production,
+            indestructible: self.indestructible,
         };
     }
 }
 
+/// The first of `candidates` that can be turned to have exits on exactly the sides `wanted` picks, turned that way.
+/// Stairs and indestructible rooms are never picked
+pub fn with_exits(
+    candidates: impl IntoIterator<Item = SimpleRoomDrawInfo>,
+    wanted: impl Fn(Direction) -> bool,
+) -> Option<SimpleRoomDrawInfo> {
+    return candidates
+        .into_iter()
+        .filter(|x| x.stairs.is_none() && !x.indestructible)
+        .find_map(|x| {
+            std::iter::successors(Some(x), |r| Some(r.rotate_right()))
+                .take(4)
+                .find(|r| {
+                    Direction::ALL
+                        .into_iter()
+                        .all(|side| r.has_exit(side) == wanted(side))
+                })
+        });
+}
+
+// rooms for tests to build with, the same as the ones that ship in assets/rooms.txt
+#[cfg(test)]
 pub mod room_type {
-    use crate::map::room::SimpleRoomDrawInfo;
+    use crate::game::ENTRANCE_SYMBOL;
+    use crate::map::room::{ROOM_BODY, SimpleRoomDrawInfo, Stairs};
+    use crate::resources::Resources;
 
     pub const DEAD_END: SimpleRoomDrawInfo = SimpleRoomDrawInfo {
         top_exit: true,
@@ -108,6 +172,12 @@ pub mod room_type {
         right_exit: false,
         bottom_exit: false,
         symbol: None,
+        stairs: None,
+        cost: Resources::gold(10),
+        traversal_cost: 1,
+        colour: ROOM_BODY,
+        production: Resources::ZERO,
+        indestructible: false,
     };
     pub const HALL: SimpleRoomDrawInfo = SimpleRoomDrawInfo {
         top_exit: true,
@@ -115,6 +185,12 @@ pub mod room_type {
         right_exit: false,
         bottom_exit: true,
         symbol: None,
+        stairs: None,
+        cost: Resources::gold(15),
+        traversal_cost: 2,
+        colour: ROOM_BODY,
+        production: Resources::ZERO,
+        indestructible: false,
     };
     pub const L: SimpleRoomDrawInfo = SimpleRoomDrawInfo {
         top_exit: true,
@@ -122,6 +198,12 @@ pub mod room_type {
         right_exit: true,
         bottom_exit: false,
         symbol: None,
+        stairs: None,
+        cost: Resources::gold(15),
+        traversal_cost: 1,
+        colour: ROOM_BODY,
+        production: Resources::ZERO,
+        indestructible: false,
     };
     pub const T: SimpleRoomDrawInfo = SimpleRoomDrawInfo {
         left_exit: true,
@@ -129,6 +211,12 @@ pub mod room_type {
         top_exit: true,
         bottom_exit: false,
         symbol: None,
+        stairs: None,
+        cost: Resources::gold(25),
+        traversal_cost: 1,
+        colour: ROOM_BODY,
+        production: Resources::ZERO,
+        indestructible: false,
     };
     pub const CROSSING: SimpleRoomDrawInfo = SimpleRoomDrawInfo {
         left_exit: true,
@@ -136,6 +224,12 @@ pub mod room_type {
         top_exit: true,
         bottom_exit: true,
         symbol: None,
+        stairs: None,
+        cost: Resources::gold(40),
+        traversal_cost: 1,
+        colour: ROOM_BODY,
+        production: Resources::ZERO,
+        indestructible: false,
     };
     pub const NO_EXIT: SimpleRoomDrawInfo = SimpleRoomDrawInfo {
         left_exit: false,
@@ -143,7 +237,91 @@ pub mod room_type {
         top_exit: false,
         bottom_exit: false,
         symbol: None,
+        stairs: None,
+        cost: Resources::gold(5),
+        traversal_cost: 1,
+        colour: ROOM_BODY,
+        production: Resources::ZERO,
+        indestructible: false,
+    };
+
+    // a stair room links to the matching stair room in the same row and column of the next floor
+    pub const STAIRS_DOWN: SimpleRoomDrawInfo = SimpleRoomDrawInfo {
+        top_exit: true,
+        left_exit: false,
+        right_exit: false,
+        bottom_exit: false,
+        symbol: Some('>'),
+        stairs: Some(Stairs::Down),
+        cost: Resources::gold(100),
+        traversal_cost: 3,
+        colour: ROOM_BODY,
+        production: Resources::ZERO,
+        indestructible: false,
+    };
+    pub const STAIRS_UP: SimpleRoomDrawInfo = SimpleRoomDrawInfo {
+        top_exit: true,
+        left_exit: false,
+        right_exit: false,
+        bottom_exit: false,
+        symbol: Some('<'),
+        stairs: Some(Stairs::Up),
+        cost: Resources::gold(100),
+        traversal_cost: 3,
+        colour: ROOM_BODY,
+        production: Resources::ZERO,
+        indestructible: false,
+    };
+
+    // the room tests usually start a game with, the way the game marks an entrance
+    pub const ENTRANCE: SimpleRoomDrawInfo = SimpleRoomDrawInfo {
+        symbol: Some(ENTRANCE_SYMBOL),
+        indestructible: true,
+        ..T
     };
 
-    pub const ALL_TYPES: [SimpleRoomDrawInfo; 6] = [DEAD_END, L, HALL, T, CROSSING, NO_EXIT];
+    pub const ALL_TYPES: [SimpleRoomDrawInfo; 8] = [
+        DEAD_END,
+        L,
+        HALL,
+        T,
+        CROSSING,
+        NO_EXIT,
+        STAIRS_DOWN,
+        STAIRS_UP,
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::{
+        Direction,
+        room::{SimpleRoomDrawInfo, room_type, with_exits},
+    };
+
+    #[test]
+    fn test_room_with_exits() {
+        let exits = |room: SimpleRoomDrawInfo| {
+            return Direction::ALL.map(|x| room.has_exit(x));
+        };
+        let hall = with_exits(room_type::ALL_TYPES, |x| {
+            x == Direction::Top || x == Direction::Bottom
+        })
+        .unwrap();
+        assert_eq!(exits(hall), [false, false, true, true]);
+        assert_eq!(hall.cost, room_type::HALL.cost);
+        let corner = with_exits(room_type::ALL_TYPES, |x| {
+            x == Direction::Left || x == Direction::Bottom
+        })
+        .unwrap();
+        assert_eq!(exits(corner), [true, false, false, true]);
+        assert_eq!(corner.cost, room_type::L.cost);
+        let end = with_exits(room_type::ALL_TYPES, |x| x == Direction::Right).unwrap();
+        assert_eq!(exits(end), [false, true, false, false]);
+        // stairs are never picked, even when nothing else fits
+        assert_eq!(
+            with_exits([room_type::STAIRS_DOWN], |x| x == Direction::Top),
+            None
+        );
+    }
 }
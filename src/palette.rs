@@ -0,0 +1,307 @@
+use macroquad::prelude::*;
+
+use crate::{
+    map::{
+        MapLevel,
+        catalogue::{RoomCatalogue, RoomDefinition},
+        room::SimpleRoomDrawInfo,
+    },
+    resources::Resources,
+};
+
+// gap around the sidebar and between its slots
+const MARGIN: f32 = 10.0;
+// largest a slot gets when there is room for it
+const MAX_SLOT_SIZE: f32 = 64.0;
+const TOOLTIP_FONT_SIZE: f32 = 20.0;
+
+/// What a left click on the map does
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tool {
+    // builds the brush in the cell clicked
+    #[default]
+    Place,
+    // removes every room in the rectangle dragged out
+    ClearArea,
+    // builds the brush in every cell dragged over
+    Paint,
+    // lays a connected corridor along the path dragged, picking and turning rooms to fit
+    Corridor,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 4] = [Tool::Place, Tool::ClearArea, Tool::Paint, Tool::Corridor];
+
+    pub fn name(self) -> &'static str {
+        return match self {
+            Tool::Place => "place",
+            Tool::ClearArea => "clear area",
+            Tool::Paint => "paint",
+            Tool::Corridor => "corridor",
+        };
+    }
+}
+
+/// How the room placed by the place and paint tools is fitted to its neighbours
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Placement {
+    // the brush exactly as the player turned it
+    #[default]
+    Manual,
+    // the brush turned to join as many neighbours as it can
+    AutoOrient,
+    // whichever room joins every neighbour facing the cell, falling back to auto orienting the brush
+    AutoConnect,
+}
+
+impl Placement {
+    pub fn next(self) -> Placement {
+        return match self {
+            Placement::Manual => Placement::AutoOrient,
+            Placement::AutoOrient => Placement::AutoConnect,
+            Placement::AutoConnect => Placement::Manual,
+        };
+    }
+
+    pub fn name(self) -> &'static str {
+        return match self {
+            Placement::Manual => "manual",
+            Placement::AutoOrient => "auto orient",
+            Placement::AutoConnect => "auto connect",
+        };
+    }
+}
+
+/// The sidebar listing every room in the catalogue, and the brush it sets: the room placed by a click, turned the
+/// way the player chose before placing it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Palette {
+    // index into the catalogue's rooms
+    pub selected: usize,
+    // quarter turns to the right, 0 to 3
+    pub turns: u8,
+    pub tool: Tool,
+    pub placement: Placement,
+}
+
+impl Palette {
+    /// The selected room turned the chosen way
+    pub fn brush(&self, catalogue: &RoomCatalogue) -> SimpleRoomDrawInfo {
+        let mut room = catalogue.rooms[self.selected].room;
+        for _ in 0..self.turns {
+            room = room.rotate_right();
+        }
+        return room;
+    }
+
+    /// The room to build at (`row`, `col`) on `level`, which is the brush unless [Palette::placement] fits it to the
+    /// neighbours
+    pub fn room_for(
+        &self,
+        catalogue: &RoomCatalogue,
+        level: &MapLevel,
+        row: usize,
+        col: usize,
+    ) -> SimpleRoomDrawInfo {
+        let brush = self.brush(catalogue);
+        return match self.placement {
+            Placement::Manual => brush,
+            Placement::AutoOrient => level.auto_orient(row, col, brush),
+            Placement::AutoConnect => level
+                .auto_connect(row, col, catalogue.rooms.iter().map(|x| x.room))
+                .unwrap_or_else(|| level.auto_orient(row, col, brush)),
+        };
+    }
+
+    pub fn rotate(&mut self, clockwise: bool) {
+        self.turns = if clockwise {
+            (self.turns + 1) % 4
+        } else {
+            (self.turns + 3) % 4
+        };
+    }
+
+    pub fn select_next(&mut self, catalogue: &RoomCatalogue) {
+        self.selected = (self.selected + 1) % catalogue.rooms.len();
+    }
+
+    /// Size of each slot so all `count` of them fit down a screen `screen_height` pixels tall
+    fn slot_size(count: usize, screen_height: f32) -> f32 {
+        let fitting = (screen_height - MARGIN) / count.max(1) as f32 - MARGIN;
+        return fitting.clamp(1.0, MAX_SLOT_SIZE);
+    }
+
+    /// Width of the sidebar, anything else drawn down the left of the screen should start right of it
+    pub fn width(count: usize, screen_height: f32) -> f32 {
+        return Palette::slot_size(count, screen_height) + MARGIN * 2.0;
+    }
+
+    /// Top left corner and size of the slot showing room `index`
+    fn slot(index: usize, count: usize, screen_height: f32) -> Rect {
+        let size = Palette::slot_size(count, screen_height);
+        return Rect::new(MARGIN, MARGIN + index as f32 * (size + MARGIN), size, size);
+    }
+
+    /// The room whose slot is under `point`
+    pub fn slot_at(point: Vec2, count: usize, screen_height: f32) -> Option<usize> {
+        return (0..count).find(|x| Palette::slot(*x, count, screen_height).contains(point));
+    }
+
+    /// Whether `point` is over the sidebar, clicks there shouldn't reach the map behind it
+    pub fn contains(point: Vec2, count: usize, screen_height: f32) -> bool {
+        let last = Palette::slot(count.saturating_sub(1), count, screen_height);
+        return point.x < Palette::width(count, screen_height) && point.y < last.bottom() + MARGIN;
+    }
+
+    /// Selects the room whose slot was clicked
+    pub fn handle_click(&mut self, point: Vec2, catalogue: &RoomCatalogue) {
+        if let Some(index) = Palette::slot_at(point, catalogue.rooms.len(), screen_height()) {
+            self.selected = index;
+        }
+    }
+
+    /// Draws every room with the selected one turned like the brush, and a tooltip for the one under `mouse`
+    pub fn draw(&self, catalogue: &RoomCatalogue, mouse: Vec2) {
+        let count = catalogue.rooms.len();
+        let last = Palette::slot(count - 1, count, screen_height());
+        draw_rectangle(
+            0.0,
+            0.0,
+            Palette::width(count, screen_height()),
+            last.bottom() + MARGIN,
+            DARKGRAY.with_alpha(0.8),
+        );
+        for (i, definition) in catalogue.rooms.iter().enumerate() {
+            let slot = Palette::slot(i, count, screen_height());
+            let room = if i == self.selected {
+                self.brush(catalogue)
+            } else {
+                definition.room
+            };
+            room.draw(slot.point(), slot.w);
+            if i == self.selected {
+                draw_rectangle_lines(slot.x, slot.y, slot.w, slot.h, 4.0, BLUE);
+            }
+        }
+        if let Some(index) = Palette::slot_at(mouse, count, screen_height()) {
+            let slot = Palette::slot(index, count, screen_height());
+            draw_tooltip(
+                &catalogue.rooms[index],
+                Vec2::new(slot.right() + MARGIN, slot.y),
+            );
+        }
+    }
+}
+
+fn draw_tooltip(definition: &RoomDefinition, top_left: Vec2) {
+    let room = &definition.room;
+    let mut lines = vec![
+        definition.name.clone(),
+        format!("cost: {}", room.cost),
+        format!("traversal cost: {}", room.traversal_cost),
+    ];
+    if room.production != Resources::ZERO {
+        lines.push(format!("produces: {}", room.production));
+    }
+    if !definition.description.is_empty() {
+        lines.push(definition.description.clone());
+    }
+    let width = lines
+        .iter()
+        .map(|x| measure_text(x, None, TOOLTIP_FONT_SIZE as u16, 1.0).width)
+        .fold(0.0, f32::max);
+    let height = lines.len() as f32 * TOOLTIP_FONT_SIZE + MARGIN;
+    draw_rectangle(
+        top_left.x,
+        top_left.y,
+        width + MARGIN * 2.0,
+        height,
+        BLACK.with_alpha(0.8),
+    );
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(
+            line,
+            top_left.x + MARGIN,
+            top_left.y + (i + 1) as f32 * TOOLTIP_FONT_SIZE,
+            TOOLTIP_FONT_SIZE,
+            WHITE,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::prelude::*;
+
+    use crate::map::{MapLevel, catalogue::RoomCatalogue, room::room_type};
+    use crate::palette::{Palette, Placement};
+
+    #[test]
+    fn test_brush_rotation() {
+        let catalogue = RoomCatalogue::built_in();
+        let mut palette = Palette::default();
+        assert_eq!(palette.brush(&catalogue), room_type::DEAD_END);
+        palette.rotate(true);
+        assert_eq!(
+            palette.brush(&catalogue),
+            room_type::DEAD_END.rotate_right()
+        );
+        palette.rotate(false);
+        palette.rotate(false);
+        assert_eq!(palette.brush(&catalogue), room_type::DEAD_END.rotate_left());
+
+        // the brush keeps its turn when another room is picked
+        palette.select_next(&catalogue);
+        assert_eq!(palette.brush(&catalogue), room_type::L.rotate_left());
+    }
+
+    #[test]
+    fn test_placement() {
+        let catalogue = RoomCatalogue::built_in();
+        let mut level = MapLevel::new(3, 2);
+        level.rooms[(0, 0)] = Some(room_type::HALL.rotate_right());
+        level.rooms[(0, 2)] = Some(room_type::HALL.rotate_right());
+        let mut palette = Palette::default();
+        assert_eq!(
+            palette.room_for(&catalogue, &level, 0, 1),
+            room_type::DEAD_END
+        );
+
+        palette.placement = palette.placement.next();
+        assert_eq!(palette.placement, Placement::AutoOrient);
+        assert_eq!(
+            palette.room_for(&catalogue, &level, 0, 1),
+            room_type::DEAD_END.rotate_right()
+        );
+
+        // auto connect picks a hall to join both sides, and nothing leads to the bottom right so the brush is used
+        palette.placement = palette.placement.next();
+        palette.rotate(true);
+        assert_eq!(
+            palette.room_for(&catalogue, &level, 0, 1),
+            room_type::HALL.rotate_right()
+        );
+        assert_eq!(
+            palette.room_for(&catalogue, &level, 1, 2),
+            room_type::DEAD_END.rotate_right()
+        );
+        assert_eq!(palette.placement.next(), Placement::Manual);
+    }
+
+    #[test]
+    fn test_slots() {
+        // plenty of space, so slots are 64 pixels with 10 between them
+        assert_eq!(Palette::slot_at(Vec2::new(20.0, 20.0), 8, 1000.0), Some(0));
+        assert_eq!(Palette::slot_at(Vec2::new(20.0, 170.0), 8, 1000.0), Some(2));
+        // between two slots
+        assert_eq!(Palette::slot_at(Vec2::new(20.0, 78.0), 8, 1000.0), None);
+        assert!(Palette::contains(Vec2::new(20.0, 78.0), 8, 1000.0));
+        assert!(!Palette::contains(Vec2::new(20.0, 700.0), 8, 1000.0));
+        assert!(!Palette::contains(Vec2::new(100.0, 20.0), 8, 1000.0));
+
+        // squeezed onto a short screen every slot still fits
+        let last = Palette::slot(7, 8, 330.0);
+        assert_eq!(last.w, 30.0);
+        assert_eq!(last.bottom() + 10.0, 330.0);
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    fmt,
+    ops::{Add, AddAssign, Mul},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Resources {
+    pub gold: u64,
+    pub mana: u64,
+    pub souls: u64,
+}
+
+impl Resources {
+    pub const ZERO: Resources = Resources {
+        gold: 0,
+        mana: 0,
+        souls: 0,
+    };
+
+    pub const fn gold(gold: u64) -> Self {
+        return Resources {
+            gold,
+            mana: 0,
+            souls: 0,
+        };
+    }
+
+    /// `percent`% of these resources, rounded down
+    pub fn percent(self, percent: u64) -> Self {
+        return Resources {
+            gold: self.gold * percent / 100,
+            mana: self.mana * percent / 100,
+            souls: self.souls * percent / 100,
+        };
+    }
+
+    pub fn saturating_sub(self, rhs: Resources) -> Self {
+        return Resources {
+            gold: self.gold.saturating_sub(rhs.gold),
+            mana: self.mana.saturating_sub(rhs.mana),
+            souls: self.souls.saturating_sub(rhs.souls),
+        };
+    }
+
+    pub fn can_afford(&self, cost: Resources) -> bool {
+        return self.gold >= cost.gold && self.mana >= cost.mana && self.souls >= cost.souls;
+    }
+
+    /// Takes `cost` out of these resources, leaving them untouched and returning false if they can't cover it
+    pub fn try_spend(&mut self, cost: Resources) -> bool {
+        if !self.can_afford(cost) {
+            return false;
+        }
+        self.gold -= cost.gold;
+        self.mana -= cost.mana;
+        self.souls -= cost.souls;
+        return true;
+    }
+}
+
+impl Add for Resources {
+    type Output = Resources;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        return Resources {
+            gold: self.gold + rhs.gold,
+            mana: self.mana + rhs.mana,
+            souls: self.souls + rhs.souls,
+        };
+    }
+}
+
+impl AddAssign for Resources {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Mul<u64> for Resources {
+    type Output = Resources;
+
+    fn mul(self, rhs: u64) -> Self::Output {
+        return Resources {
+            gold: self.gold * rhs,
+            mana: self.mana * rhs,
+            souls: self.souls * rhs,
+        };
+    }
+}
+
+impl fmt::Display for Resources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "gold: {}, mana: {}, souls: {}",
+            self.gold, self.mana, self.souls
+        )
+    }
+}
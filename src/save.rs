@@ -0,0 +1,830 @@
+use std::{collections::BTreeMap, fmt, fs, io, path::Path};
+
+use macroquad::color::Color;
+
+use crate::{
+    adventurer::{Adventurer, AdventurerState, SimRng},
+    game::GameState,
+    map::{
+        Direction, Dungeon, MapLevel, TraversalMode,
+        room::{ROOM_BODY, SimpleRoomDrawInfo, Stairs},
+    },
+    resources::Resources,
+};
+
+pub const SAVE_PATH: &str = "savegame.txt";
+pub const SAVE_MAGIC: &str = "idle-dungeon-maker save";
+// bump whenever the format changes, along with a new entry in MIGRATIONS and a new fixture in fixtures/saves
+pub const CURRENT_VERSION: u32 = 5;
+// no save the game writes comes close to these, they stop a damaged size line from allocating without limit
+const MAX_SIDE: usize = 4096;
+const MAX_CELLS: usize = 1 << 22;
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    NotASave,
+    NewerVersion { found: u32, supported: u32 },
+    Corrupt { line: usize, reason: String },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "couldn't access the save file: {e}"),
+            SaveError::NotASave => write!(f, "the file is not an idle-dungeon-maker save"),
+            SaveError::NewerVersion { found, supported } => write!(
+                f,
+                "the save is from a newer version of the game (save version {found}, this game supports up to {supported})"
+            ),
+            // line 0 is used for problems with the save as a whole
+            SaveError::Corrupt { line: 0, reason } => write!(f, "the save is corrupt: {reason}"),
+            SaveError::Corrupt { line, reason } => {
+                write!(f, "the save is corrupt on line {line}: {reason}")
+            }
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> Self {
+        return SaveError::Io(e);
+    }
+}
+
+/// Writes the game out as plain text, one `key values...` entry per line under a magic and version header.
+/// Only non-empty rooms are written
+pub fn serialize(game: &GameState) -> String {
+    let mut lines = vec![SAVE_MAGIC.to_string(), format!("version {CURRENT_VERSION}")];
+    let top = game.get_map_level(0);
+    lines.push(format!("saved_at {}", game.last_seen_unix_secs));
+    lines.push(format!(
+        "size {} {} {}",
+        top.width(),
+        top.height(),
+        game.floor_count()
+    ));
+    lines.push(format!("resources {}", resources_to_string(game.resources)));
+    lines.push(format!(
+        "timers {} {} {}",
+        game.income_timer, game.adventurers.spawn_timer, game.adventurers.rng.state
+    ));
+    lines.push(format!(
+        "traversal {}",
+        match game.traversal_mode {
+            TraversalMode::Breadth => "breadth",
+            TraversalMode::Weighted => "weighted",
+        }
+    ));
+    for (row, col) in game.entrance_rowcols.iter() {
+        lines.push(format!("entrance {row} {col}"));
+    }
+    for ((row, col), room) in game.entrance_originals.iter() {
+        lines.push(format!(
+            "entrance_room {row} {col} {}",
+            room_to_string(room)
+        ));
+    }
+    for (floor, level) in game.dungeon.floors.iter().enumerate() {
+        for (row, col, room) in level.rooms.iter_some() {
+            lines.push(format!("room {floor} {row} {col} {}", room_to_string(room)));
+        }
+    }
+    for adventurer in game.adventurers.active.iter() {
+        lines.push(format!(
+            "adventurer {} {} {} {} {} {}",
+            adventurer.floor,
+            adventurer.row,
+            adventurer.col,
+            match adventurer.state {
+                AdventurerState::Exploring => "exploring",
+                AdventurerState::Returning => "returning",
+            },
+            adventurer.stamina,
+            adventurer.ticks_in_room
+        ));
+    }
+    // lets loading tell a save that was cut short apart from one that just has nothing more in it
+    lines.push("end".to_string());
+    lines.push(String::new());
+    return lines.join("\n");
+}
+
+/// A save split into its entries, migrations upgrade saves in this form before they are turned into a [GameState]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveDocument {
+    pub version: u32,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    // where the entry came from in the original file, for error messages
+    pub line: usize,
+    pub key: String,
+    pub values: Vec<String>,
+}
+
+/// Upgrades a document's entries by exactly one version, [SaveDocument::migrate] updates the version number
+type Migration = fn(&mut SaveDocument) -> Result<(), SaveError>;
+
+// the colour every room had before version 3, as saved
+const V2_ROOM_COLOUR: &str = "0.9 0.16 0.22";
+
+// MIGRATIONS[i] turns a version i + 1 save into a version i + 2 save
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize - 1] = [
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+];
+
+/// Version 2 gave rooms a traversal cost and saved the traversal mode, older rooms get the cost of their type
+fn migrate_v1_to_v2(document: &mut SaveDocument) -> Result<(), SaveError> {
+    for entry in document.entries.iter_mut() {
+        if entry.key != "room" {
+            continue;
+        }
+        // floor, row and col come before the room itself
+        let mut values = Values {
+            line: entry.line,
+            inner: entry.values[3.min(entry.values.len())..].iter(),
+        };
+        let room = values.next_room_without_traversal_cost()?;
+        let traversal_cost = v2_traversal_cost(&room);
+        entry.values.push(traversal_cost.to_string());
+    }
+    // right after the timers, where newer saves write it
+    let position = document
+        .entries
+        .iter()
+        .position(|x| x.key == "timers")
+        .map_or(document.entries.len(), |x| x + 1);
+    document.entries.insert(
+        position,
+        Entry {
+            line: 0,
+            key: "traversal".to_string(),
+            values: vec!["breadth".to_string()],
+        },
+    );
+    return Ok(());
+}
+
+/// The traversal cost version 2 gave each of the room types there were then, told apart by their exits and stairs
+/// since rooms didn't save their type. Fixed here so changes to the catalogue don't change what old saves load as
+fn v2_traversal_cost(room: &SimpleRoomDrawInfo) -> u32 {
+    let exits: Vec<Direction> = Direction::ALL
+        .into_iter()
+        .filter(|x| room.has_exit(*x))
+        .collect();
+    return match (room.stairs, exits.as_slice()) {
+        // stairs
+        (Some(_), [_]) => 3,
+        // hall
+        (None, [a, b]) if a.opposite() == *b => 2,
+        _ => 1,
+    };
+}
+
+/// Version 3 gave rooms a colour and production. Every room type before then was drawn in the same colour and
+/// produced nothing
+fn migrate_v2_to_v3(document: &mut SaveDocument) -> Result<(), SaveError> {
+    for entry in document.entries.iter_mut() {
+        if entry.key != "room" {
+            continue;
+        }
+        let mut values = Values {
+            line: entry.line,
+            inner: entry.values[3.min(entry.values.len())..].iter(),
+        };
+        values.next_room_without_traversal_cost()?;
+        entry.values.extend(
+            format!("{V2_ROOM_COLOUR} {}", resources_to_string(Resources::ZERO))
+                .split_whitespace()
+                .map(String::from),
+        );
+    }
+    return Ok(());
+}
+
+/// Version 4 saved whether rooms are indestructible. Before then only entrances were protected, by the game
+/// refusing to build over their symbol
+fn migrate_v3_to_v4(document: &mut SaveDocument) -> Result<(), SaveError> {
+    let mut entrances = Vec::new();
+    for entry in document.entries.iter().filter(|x| x.key == "entrance") {
+        let mut values = Values {
+            line: entry.line,
+            inner: entry.values.iter(),
+        };
+        entrances.push((
+            values.next_parsed::<usize>("row")?,
+            values.next_parsed::<usize>("col")?,
+        ));
+    }
+    for entry in document.entries.iter_mut() {
+        if entry.key != "room" {
+            continue;
+        }
+        let mut values = Values {
+            line: entry.line,
+            inner: entry.values.iter(),
+        };
+        let cell = (
+            values.next_parsed::<usize>("floor")?,
+            values.next_parsed::<usize>("row")?,
+            values.next_parsed::<usize>("col")?,
+        );
+        let entrance = cell.0 == 0 && entrances.contains(&(cell.1, cell.2));
+        entry
+            .values
+            .push(if entrance { "1" } else { "0" }.to_string());
+    }
+    return Ok(());
+}
+
+/// Version 5 saved the rooms entrances were made from. Older saves didn't keep them, so their entrances go back to
+/// plain rooms when removed, as they always did
+fn migrate_v4_to_v5(_document: &mut SaveDocument) -> Result<(), SaveError> {
+    return Ok(());
+}
+
+impl SaveDocument {
+    /// Splits a save into entries without interpreting them, checking only the header and that the save is complete
+    pub fn parse(text: &str) -> Result<Self, SaveError> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+        if lines
+            .next()
+            .is_none_or(|(_, line)| line.trim() != SAVE_MAGIC)
+        {
+            return Err(SaveError::NotASave);
+        }
+        let version = match lines
+            .next()
+            .map(|(_, line)| line.split_whitespace().collect::<Vec<_>>())
+        {
+            Some(parts) if matches!(parts[..], ["version", _]) => {
+                parts[1].parse::<u32>().map_err(|_| {
+                    corrupt(2, &format!("'{}' is not a valid version number", parts[1]))
+                })?
+            }
+            _ => return Err(corrupt(2, "missing version")),
+        };
+        if version > CURRENT_VERSION {
+            return Err(SaveError::NewerVersion {
+                found: version,
+                supported: CURRENT_VERSION,
+            });
+        }
+        if version == 0 {
+            return Err(corrupt(2, "version 0 does not exist"));
+        }
+
+        let mut entries = Vec::new();
+        let mut ended = false;
+        for (line, text) in lines {
+            let mut parts = text.split_whitespace().map(String::from);
+            let Some(key) = parts.next() else {
+                continue;
+            };
+            if ended {
+                return Err(corrupt(line, "unexpected entry after the end"));
+            }
+            if key == "end" {
+                ended = true;
+                continue;
+            }
+            entries.push(Entry {
+                line,
+                key,
+                values: parts.collect(),
+            });
+        }
+        if !ended {
+            return Err(corrupt(0, "the save is incomplete"));
+        }
+        return Ok(SaveDocument { version, entries });
+    }
+
+    /// Runs every migration needed to bring the document up to [CURRENT_VERSION]
+    pub fn migrate(&mut self) -> Result<(), SaveError> {
+        while self.version < CURRENT_VERSION {
+            MIGRATIONS[self.version as usize - 1](self)?;
+            self.version += 1;
+        }
+        return Ok(());
+    }
+}
+
+pub fn deserialize(text: &str) -> Result<GameState, SaveError> {
+    let mut document = SaveDocument::parse(text)?;
+    document.migrate()?;
+    return build_game(&document);
+}
+
+/// Turns a document at [CURRENT_VERSION] into the game it describes
+fn build_game(document: &SaveDocument) -> Result<GameState, SaveError> {
+    let mut saved_at = None;
+    let mut size = None;
+    let mut size_line = 0;
+    let mut resources = Resources::ZERO;
+    let mut timers = (0, 0, 1);
+    let mut traversal_mode = TraversalMode::default();
+    let mut entrances = Vec::new();
+    let mut entrance_rooms = Vec::new();
+    let mut rooms = Vec::new();
+    let mut adventurers = Vec::new();
+    for entry in document.entries.iter() {
+        let line_number = entry.line;
+        let mut values = Values {
+            line: line_number,
+            inner: entry.values.iter(),
+        };
+        match entry.key.as_str() {
+            "saved_at" => saved_at = Some(values.next_parsed::<u64>("timestamp")?),
+            "size" => {
+                size_line = line_number;
+                size = Some((
+                    values.next_parsed::<usize>("width")?,
+                    values.next_parsed::<usize>("height")?,
+                    values.next_parsed::<usize>("floor count")?,
+                ))
+            }
+            "resources" => resources = values.next_resources()?,
+            "timers" => {
+                timers = (
+                    values.next_parsed::<u32>("income timer")?,
+                    values.next_parsed::<u32>("spawn timer")?,
+                    values.next_parsed::<u64>("rng state")?,
+                )
+            }
+            "traversal" => {
+                traversal_mode = match values.next_str("traversal mode")? {
+                    "breadth" => TraversalMode::Breadth,
+                    "weighted" => TraversalMode::Weighted,
+                    value => {
+                        return Err(corrupt(
+                            line_number,
+                            &format!("'{value}' is not a valid traversal mode"),
+                        ));
+                    }
+                }
+            }
+            "entrance" => entrances.push((
+                line_number,
+                values.next_parsed::<usize>("row")?,
+                values.next_parsed::<usize>("col")?,
+            )),
+            "entrance_room" => entrance_rooms.push((
+                line_number,
+                values.next_parsed::<usize>("row")?,
+                values.next_parsed::<usize>("col")?,
+                values.next_room()?,
+            )),
+            "room" => rooms.push((
+                line_number,
+                values.next_parsed::<usize>("floor")?,
+                values.next_parsed::<usize>("row")?,
+                values.next_parsed::<usize>("col")?,
+                values.next_room()?,
+            )),
+            "adventurer" => adventurers.push((line_number, values.next_adventurer()?)),
+            key => return Err(corrupt(line_number, &format!("unknown entry '{key}'"))),
+        }
+        values.finish()?;
+    }
+
+    let (width, height, floor_count) = size.ok_or_else(|| corrupt(0, "missing size"))?;
+    if width == 0 || height == 0 || floor_count == 0 {
+        return Err(corrupt(0, "the dungeon has no space in it"));
+    }
+    let cells = width
+        .checked_mul(height)
+        .and_then(|x| x.checked_mul(floor_count));
+    if width.max(height).max(floor_count) > MAX_SIDE || cells.is_none_or(|x| x > MAX_CELLS) {
+        return Err(corrupt(
+            size_line,
+            &format!("a {width} by {height} dungeon with {floor_count} floors is too big"),
+        ));
+    }
+    let in_bounds = |line: usize, floor: usize, row: usize, col: usize| {
+        if floor >= floor_count || row >= height || col >= width {
+            return Err(corrupt(
+                line,
+                &format!("({floor}, {row}, {col}) is outside the dungeon"),
+            ));
+        }
+        return Ok(());
+    };
+
+    let mut dungeon = Dungeon {
+        floors: (0..floor_count)
+            .map(|_| MapLevel::new(width, height))
+            .collect(),
+    };
+    for (line, floor, row, col, room) in rooms {
+        in_bounds(line, floor, row, col)?;
+        dungeon.floors[floor].rooms[(row, col)] = Some(room);
+    }
+    let mut entrance_rowcols = Vec::new();
+    for (line, row, col) in entrances {
+        in_bounds(line, 0, row, col)?;
+        entrance_rowcols.push((row, col));
+    }
+    let mut entrance_originals = BTreeMap::new();
+    for (line, row, col, room) in entrance_rooms {
+        in_bounds(line, 0, row, col)?;
+        if !entrance_rowcols.contains(&(row, col)) {
+            return Err(corrupt(line, &format!("({row}, {col}) is not an entrance")));
+        }
+        entrance_originals.insert((row, col), room);
+    }
+    for (line, adventurer) in adventurers.iter() {
+        in_bounds(*line, adventurer.floor, adventurer.row, adventurer.col)?;
+    }
+
+    let mut game = GameState::from_dungeon(dungeon, entrance_rowcols);
+    game.entrance_originals = entrance_originals;
+    game.resources = resources;
+    game.income_timer = timers.0;
+    game.adventurers.spawn_timer = timers.1;
+    game.adventurers.rng = SimRng::new(timers.2);
+    game.set_traversal_mode(traversal_mode);
+    game.adventurers.active = adventurers.into_iter().map(|(_, x)| x).collect();
+    game.last_seen_unix_secs = saved_at.ok_or_else(|| corrupt(0, "missing saved_at"))?;
+    return Ok(game);
+}
+
+/// Saves by writing a temporary file next to `path` and moving it into place, so a crash mid-write never leaves
+/// a half written save behind
+pub fn save_to_file(game: &GameState, path: &Path) -> Result<(), SaveError> {
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, serialize(game))?;
+    fs::rename(&temp_path, path)?;
+    return Ok(());
+}
+
+/// Loads the save at `path`, None when there is no save yet
+pub fn load_from_file(path: &Path) -> Result<Option<GameState>, SaveError> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    return deserialize(&text).map(Some);
+}
+
+fn corrupt(line: usize, reason: &str) -> SaveError {
+    return SaveError::Corrupt {
+        line,
+        reason: reason.to_string(),
+    };
+}
+
+fn resources_to_string(resources: Resources) -> String {
+    return format!("{} {} {}", resources.gold, resources.mana, resources.souls);
+}
+
+fn room_to_string(room: &SimpleRoomDrawInfo) -> String {
+    let exits: String = [
+        room.left_exit,
+        room.right_exit,
+        room.top_exit,
+        room.bottom_exit,
+    ]
+    .iter()
+    .map(|x| if *x { '1' } else { '0' })
+    .collect();
+    return format!(
+        "{exits} {} {} {} {} {} {} {}",
+        room.symbol.map_or("none".to_string(), |x| x.to_string()),
+        match room.stairs {
+            None => "none",
+            Some(Stairs::Up) => "up",
+            Some(Stairs::Down) => "down",
+        },
+        resources_to_string(room.cost),
+        room.traversal_cost,
+        colour_to_string(room.colour),
+        resources_to_string(room.production),
+        if room.indestructible { 1 } else { 0 }
+    );
+}
+
+fn colour_to_string(colour: Color) -> String {
+    return format!("{} {} {}", colour.r, colour.g, colour.b);
+}
+
+/// The whitespace separated values following an entry's key
+struct Values<'a> {
+    line: usize,
+    inner: std::slice::Iter<'a, String>,
+}
+
+impl<'a> Values<'a> {
+    fn next_str(&mut self, what: &str) -> Result<&'a str, SaveError> {
+        return self
+            .inner
+            .next()
+            .map(String::as_str)
+            .ok_or_else(|| corrupt(self.line, &format!("missing {what}")));
+    }
+
+    fn next_parsed<T: std::str::FromStr>(&mut self, what: &str) -> Result<T, SaveError> {
+        let value = self.next_str(what)?;
+        return value
+            .parse()
+            .map_err(|_| corrupt(self.line, &format!("'{value}' is not a valid {what}")));
+    }
+
+    fn next_resources(&mut self) -> Result<Resources, SaveError> {
+        return Ok(Resources {
+            gold: self.next_parsed("gold amount")?,
+            mana: self.next_parsed("mana amount")?,
+            souls: self.next_parsed("souls amount")?,
+        });
+    }
+
+    fn next_colour(&mut self) -> Result<Color, SaveError> {
+        return Ok(Color::new(
+            self.next_parsed("red component")?,
+            self.next_parsed("green component")?,
+            self.next_parsed("blue component")?,
+            1.0,
+        ));
+    }
+
+    fn next_room(&mut self) -> Result<SimpleRoomDrawInfo, SaveError> {
+        let room = self.next_room_without_traversal_cost()?;
+        return Ok(SimpleRoomDrawInfo {
+            traversal_cost: self.next_parsed("traversal cost")?,
+            colour: self.next_colour()?,
+            production: self.next_resources()?,
+            indestructible: match self.next_str("indestructible flag")? {
+                "0" => false,
+                "1" => true,
+                value => {
+                    return Err(corrupt(
+                        self.line,
+                        &format!("'{value}' is not a valid indestructible flag"),
+                    ));
+                }
+            },
+            ..room
+        });
+    }
+
+    /// A room as saved before version 2, its traversal cost is left at 1 and it is drawn in [ROOM_BODY] without
+    /// producing anything or being indestructible
+    fn next_room_without_traversal_cost(&mut self) -> Result<SimpleRoomDrawInfo, SaveError> {
+        let exits: Vec<bool> = self.next_str("exits")?.chars().map(|x| x == '1').collect();
+        if exits.len() != 4 {
+            return Err(corrupt(self.line, "exits must be 4 digits"));
+        }
+        let symbol = match self.next_str("symbol")? {
+            "none" => None,
+            value => {
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(x), None) => Some(x),
+                    _ => {
+                        return Err(corrupt(
+                            self.line,
+                            &format!("'{value}' is not a valid symbol"),
+                        ));
+                    }
+                }
+            }
+        };
+        let stairs = match self.next_str("stairs")? {
+            "none" => None,
+            "up" => Some(Stairs::Up),
+            "down" => Some(Stairs::Down),
+            value => {
+                return Err(corrupt(
+                    self.line,
+                    &format!("'{value}' is not a valid stairs direction"),
+                ));
+            }
+        };
+        return Ok(SimpleRoomDrawInfo {
+            left_exit: exits[0],
+            right_exit: exits[1],
+            top_exit: exits[2],
+            bottom_exit: exits[3],
+            symbol,
+            stairs,
+            cost: self.next_resources()?,
+            traversal_cost: 1,
+            colour: ROOM_BODY,
+            production: Resources::ZERO,
+            indestructible: false,
+        });
+    }
+
+    fn next_adventurer(&mut self) -> Result<Adventurer, SaveError> {
+        let floor = self.next_parsed("floor")?;
+        let row = self.next_parsed("row")?;
+        let col = self.next_parsed("col")?;
+        let state = match self.next_str("adventurer state")? {
+            "exploring" => AdventurerState::Exploring,
+            "returning" => AdventurerState::Returning,
+            value => {
+                return Err(corrupt(
+                    self.line,
+                    &format!("'{value}' is not a valid adventurer state"),
+                ));
+            }
+        };
+        let mut adventurer = Adventurer::new(floor, row, col);
+        adventurer.state = state;
+        adventurer.stamina = self.next_parsed("stamina")?;
+        adventurer.ticks_in_room = self.next_parsed("ticks in room")?;
+        return Ok(adventurer);
+    }
+
+    fn finish(&mut self) -> Result<(), SaveError> {
+        return match self.inner.next() {
+            Some(extra) => Err(corrupt(self.line, &format!("unexpected '{extra}'"))),
+            None => Ok(()),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adventurer::{Adventurer, AdventurerState, SimRng};
+    use crate::game::{ENTRANCE_COST, GameState};
+    use crate::map::TraversalMode;
+    use crate::map::room::{SimpleRoomDrawInfo, room_type};
+    use crate::resources::Resources;
+    use crate::save::{
+        CURRENT_VERSION, SAVE_MAGIC, SaveDocument, SaveError, deserialize, serialize,
+    };
+
+    fn sample_game() -> GameState {
+        let mut game = GameState::new(
+            3,
+            2,
+            1,
+            1,
+            SimpleRoomDrawInfo {
+                symbol: Some('E'),
+                cost: Resources::ZERO,
+                ..room_type::T
+            },
+        );
+        game.add_floor();
+        game.update_room(0, 1, 2, Some(room_type::STAIRS_DOWN.rotate_left()));
+        game.update_room(1, 1, 2, Some(room_type::STAIRS_UP));
+        game.update_room(0, 0, 1, Some(room_type::HALL));
+        game.resources = Resources {
+            gold: 123,
+            mana: 4,
+            souls: 5,
+        };
+        game.income_timer = 7;
+        game.adventurers.spawn_timer = 37;
+        game.adventurers.rng = SimRng::new(12345);
+        let mut returning = Adventurer::new(0, 1, 2);
+        returning.state = AdventurerState::Returning;
+        returning.stamina = 0;
+        game.adventurers.active.push(returning);
+        let mut exploring = Adventurer::new(1, 1, 2);
+        exploring.stamina = 4;
+        exploring.ticks_in_room = 8;
+        game.adventurers.active.push(exploring);
+        game.last_seen_unix_secs = 1_700_000_000;
+        return game;
+    }
+
+    // one fixture for every save version that has been released, all of them describing sample_game()
+    const FIXTURES: [(u32, &str); 5] = [
+        (1, include_str!("../fixtures/saves/v1.txt")),
+        (2, include_str!("../fixtures/saves/v2.txt")),
+        (3, include_str!("../fixtures/saves/v3.txt")),
+        (4, include_str!("../fixtures/saves/v4.txt")),
+        (5, include_str!("../fixtures/saves/v5.txt")),
+    ];
+
+    #[test]
+    fn test_golden_save() {
+        let (version, latest) = FIXTURES[FIXTURES.len() - 1];
+        assert_eq!(
+            version, CURRENT_VERSION,
+            "add a fixture for the new save version"
+        );
+        assert_eq!(serialize(&sample_game()), latest);
+    }
+
+    #[test]
+    fn test_fixtures_migrate_to_current() {
+        let (_, latest) = FIXTURES[FIXTURES.len() - 1];
+        for (version, fixture) in FIXTURES {
+            let document = SaveDocument::parse(fixture).unwrap();
+            assert_eq!(document.version, version);
+            let loaded = deserialize(fixture)
+                .unwrap_or_else(|e| panic!("version {version} fixture failed to load: {e}"));
+            assert_eq!(serialize(&loaded), latest, "migrating version {version}");
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut game = sample_game();
+        for _ in 0..137 {
+            game.tick();
+        }
+        assert!(!game.adventurers.active.is_empty());
+        game.set_traversal_mode(TraversalMode::Weighted);
+        game.resources += ENTRANCE_COST;
+        game.add_entrance(0, 0, 1).unwrap();
+
+        let loaded = deserialize(&serialize(&game)).unwrap();
+        assert_eq!(loaded.dungeon, game.dungeon);
+        assert_eq!(loaded.entrance_rowcols, game.entrance_rowcols);
+        assert_eq!(loaded.entrance_originals, game.entrance_originals);
+        assert_eq!(loaded.resources, game.resources);
+        assert_eq!(loaded.income_timer, game.income_timer);
+        assert_eq!(loaded.adventurers.spawn_timer, game.adventurers.spawn_timer);
+        assert_eq!(loaded.adventurers.rng, game.adventurers.rng);
+        assert_eq!(loaded.last_seen_unix_secs, game.last_seen_unix_secs);
+        assert_eq!(loaded.traversal_mode, TraversalMode::Weighted);
+        assert_eq!(loaded.traversal_info, game.traversal_info);
+        assert_eq!(
+            loaded.adventurers.active.len(),
+            game.adventurers.active.len()
+        );
+        assert_eq!(loaded.income(), game.income());
+        assert_eq!(serialize(&loaded), serialize(&game));
+    }
+
+    #[test]
+    fn test_load_errors() {
+        assert!(matches!(
+            deserialize("not a save\n"),
+            Err(SaveError::NotASave)
+        ));
+        assert!(matches!(
+            deserialize(&format!("{SAVE_MAGIC}\nversion {}\n", CURRENT_VERSION + 1)),
+            Err(SaveError::NewerVersion { found, supported })
+                if found == CURRENT_VERSION + 1 && supported == CURRENT_VERSION
+        ));
+
+        let good = FIXTURES[FIXTURES.len() - 1].1;
+        let truncated: String = good.lines().take(4).collect::<Vec<_>>().join("\n");
+        assert!(matches!(
+            deserialize(&truncated),
+            Err(SaveError::Corrupt { .. })
+        ));
+
+        // a damaged size is turned down before anything is made that big
+        for size in ["size 99999999999 2 2", "size 3000 3000 2", "size 3 2 5000"] {
+            match deserialize(&good.replace("size 3 2 2", size)) {
+                Err(SaveError::Corrupt { line, reason }) => {
+                    assert_eq!(line, 4);
+                    assert!(reason.contains("too big"));
+                }
+                _ => panic!("expected a corrupt save for '{size}'"),
+            }
+        }
+
+        let bad_room = good.replace("room 1 1 2 0010", "room 1 1 9 0010");
+        assert!(matches!(
+            deserialize(&bad_room),
+            Err(SaveError::Corrupt { line, .. }) if line > 2
+        ));
+
+        // only entrances remember the room they were made from
+        let stray_original = good.replace(
+            "entrance 1 1\n",
+            "entrance 1 1\nentrance_room 0 1 0011 none none 15 0 0 2 0.9 0.16 0.22 0 0 0 0\n",
+        );
+        match deserialize(&stray_original) {
+            Err(SaveError::Corrupt { line, reason }) => {
+                assert_eq!(line, 9);
+                assert!(reason.contains("not an entrance"));
+            }
+            _ => panic!("expected a corrupt save"),
+        }
+
+        let garbage: String = good
+            .lines()
+            .map(|x| {
+                if x.starts_with("resources ") {
+                    "resources lots 0 0"
+                } else {
+                    x
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        match deserialize(&garbage) {
+            Err(SaveError::Corrupt { line, reason }) => {
+                assert_eq!(line, 5);
+                assert!(reason.contains("lots"));
+            }
+            _ => panic!("expected a corrupt save"),
+        }
+    }
+}